@@ -0,0 +1,129 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+
+use crate::config::Settings;
+use crate::files::{self, SourceFile};
+use crate::finding::Package;
+use crate::workspace::Member;
+
+// 按扩展名识别；`.crate` 是 crates.io 发布的 tar.gz
+const TAR_GZ_EXTENSIONS: [&str; 3] = [".crate", ".tar.gz", ".tgz"];
+
+/// Whether `path` is an archive whose Rust files are scanned without
+/// unpacking it: a `.crate`, `.tar.gz`, `.tgz` or `.zip` file.
+pub fn is_archive(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    path.is_file() && (TAR_GZ_EXTENSIONS.iter().any(|extension| name.ends_with(extension)) || name.ends_with(".zip"))
+}
+
+/// Reads the Rust files in the archive at `path` that the filters of
+/// `settings` accept into memory. Each file's path is the archive's path
+/// followed by its path in the archive, e.g.
+/// `serde-1.0.200.crate/serde-1.0.200/src/lib.rs`.
+pub fn collect_files(path: &Path, settings: &Settings, files: &mut Vec<SourceFile>) -> Result<()> {
+    let root = path.display().to_string();
+    let count = files.len();
+    for_each_entry(path, |entry, content| {
+        if files::accepts(settings, entry) {
+            match String::from_utf8(content()?) {
+                Ok(content) => files.push(SourceFile {
+                    root: root.clone(),
+                    path: path.join(entry).display().to_string(),
+                    content: Some(Arc::from(content)),
+                }),
+                Err(_) => log::warn!("{}: {} is not valid UTF-8", root, entry.display()),
+            }
+        }
+        Ok(true)
+    })?;
+    log::debug!("{}: {} Rust files", root, files.len() - count);
+    Ok(())
+}
+
+/// The package in the archive at `path`, from the `Cargo.toml` closest to
+/// the top of the archive, so that findings in a `.crate` file are attributed
+/// to the published crate. `None` if the archive has no package manifest.
+pub fn member(path: &Path) -> Result<Option<Member>> {
+    let mut manifest = None;
+    for_each_entry(path, |entry, content| {
+        // `.crate` 中的清单是 `名字-版本/Cargo.toml`
+        if entry.file_name().is_some_and(|name| name == "Cargo.toml") && entry.components().count() <= 2 {
+            manifest = Some((entry.to_path_buf(), content()?));
+            return Ok(false);
+        }
+        Ok(true)
+    })?;
+    let Some((entry, content)) = manifest else {
+        return Ok(None);
+    };
+    let manifest: toml::Table = String::from_utf8(content)
+        .ok()
+        .and_then(|content| toml::from_str(&content).ok())
+        .with_context(|| format!("invalid manifest {}", path.join(&entry).display()))?;
+    let Some(package) = manifest.get("package").and_then(|package| package.as_table()) else {
+        return Ok(None);
+    };
+    let field = |name: &str| package.get(name).and_then(|value| value.as_str()).map(str::to_string);
+    let Some(name) = field("name") else {
+        return Ok(None);
+    };
+    let library = manifest.get("lib").and_then(|lib| lib.get("name")).and_then(|name| name.as_str());
+    Ok(Some(Member {
+        root: path.to_path_buf(),
+        crate_name: library.unwrap_or(&name).replace('-', "_"),
+        package: Package {
+            version: field("version").unwrap_or_default(),
+            // 没有写 edition 的包是 2015 版
+            edition: field("edition").unwrap_or_else(|| "2015".to_string()),
+            name,
+            dependency: false,
+        },
+    }))
+}
+
+// 依次把每个普通文件在压缩包中的路径和读取其内容的函数交给 visit，
+// 只有需要时才读取内容；visit 返回 false 时停止
+fn for_each_entry(
+    path: &Path,
+    mut visit: impl FnMut(&Path, &mut dyn FnMut() -> io::Result<Vec<u8>>) -> io::Result<bool>,
+) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let context = || format!("failed to read archive {}", path.display());
+    if path.to_string_lossy().ends_with(".zip") {
+        let mut archive = zip::ZipArchive::new(file).with_context(context)?;
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).with_context(context)?;
+            // enclosed_name 拒绝 `..` 和绝对路径
+            let Some(name) = entry.enclosed_name().filter(|_| entry.is_file()) else {
+                continue;
+            };
+            if !visit(&name, &mut || read(&mut entry)).with_context(context)? {
+                break;
+            }
+        }
+    } else {
+        let mut archive = tar::Archive::new(GzDecoder::new(file));
+        for entry in archive.entries().with_context(context)? {
+            let mut entry = entry.with_context(context)?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name: PathBuf = entry.path().with_context(context)?.into_owned();
+            if !visit(&name, &mut || read(&mut entry)).with_context(context)? {
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut content = Vec::new();
+    reader.read_to_end(&mut content)?;
+    Ok(content)
+}
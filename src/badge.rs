@@ -0,0 +1,94 @@
+use std::fs;
+use std::io::{self, Write};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::json;
+
+use crate::cli::BadgeArgs;
+use crate::report::{self, Summary};
+
+const LABEL: &str = "unchecked APIs with safe counterparts";
+
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BadgeFormat {
+    // 可以直接放进 README 的图片
+    #[default]
+    Svg,
+    // shields.io endpoint 徽章使用的 json
+    Shields,
+}
+
+/// Writes a badge showing the share of unchecked functions in a JSON report
+/// that have a safe counterpart.
+pub fn run(args: &BadgeArgs) -> Result<()> {
+    let findings = report::read_json(&args.input)?;
+    let coverage = Summary::new(&findings).coverage();
+    let message = format!("{:.0}%", coverage.floor());
+    let badge = match args.format {
+        BadgeFormat::Svg => svg(&message, hex(color(coverage))),
+        BadgeFormat::Shields => {
+            let endpoint = json!({
+                "schemaVersion": 1,
+                "label": LABEL,
+                "message": message,
+                "color": color(coverage),
+            });
+            format!("{}\n", serde_json::to_string_pretty(&endpoint)?)
+        }
+    };
+
+    match &args.output {
+        Some(path) => fs::write(path, badge).with_context(|| format!("failed to write {}", path.display()))?,
+        None => io::stdout().write_all(badge.as_bytes())?,
+    }
+    Ok(())
+}
+
+// shields.io 的颜色名，svg 中换成对应的色值
+fn color(coverage: f64) -> &'static str {
+    match coverage {
+        c if c >= 90.0 => "brightgreen",
+        c if c >= 75.0 => "green",
+        c if c >= 50.0 => "yellow",
+        c if c >= 25.0 => "orange",
+        _ => "red",
+    }
+}
+
+fn hex(color: &str) -> &'static str {
+    match color {
+        "brightgreen" => "#4c1",
+        "green" => "#97ca00",
+        "yellow" => "#dfb317",
+        "orange" => "#fe7d37",
+        _ => "#e05d44",
+    }
+}
+
+// 平面风格的徽章，按每个字符约 6.5 像素估算文字宽度
+fn svg(message: &str, color: &str) -> String {
+    let text_width = |text: &str| (text.chars().count() as f64 * 6.5).ceil() as usize + 10;
+    let label_width = text_width(LABEL);
+    let message_width = text_width(message);
+    let width = label_width + message_width;
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{LABEL}: {message}">
+  <title>{LABEL}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+    <text x="{label_x}" y="14">{LABEL}</text>
+    <text x="{message_x}" y="14">{message}</text>
+  </g>
+</svg>
+"##,
+        label_x = label_width / 2,
+        message_x = label_width + message_width / 2,
+    )
+}
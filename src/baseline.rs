@@ -0,0 +1,78 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::finding::{Finding, FindingKind};
+use crate::report::{self, OutputFormat, ReportOptions};
+
+/// Findings recorded by an earlier `--update-baseline` run.
+///
+/// A finding is identified by its [`key`], so a known unchecked function
+/// whose safe counterpart changes is not reported again, while a method of
+/// the same name on another type in the same file is.
+pub struct Baseline {
+    known: HashSet<Key>,
+}
+
+/// What identifies a finding across runs: its file, its name including the
+/// type or trait it belongs to (`Type::new_unchecked`), and its kind.
+pub type Key = (String, String, FindingKind);
+
+/// The [`Key`] of a finding. A call to `get_unchecked` and its definition
+/// in the same file are different findings.
+pub fn key(finding: &Finding) -> Key {
+    (finding.file.clone(), finding.name(), finding.kind)
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self> {
+        let findings = report::read_json(path).context("failed to load baseline")?;
+        let known = findings.iter().map(key).collect();
+        Ok(Baseline { known })
+    }
+
+    // 基线文件就是 json 格式的报告
+    pub fn write(path: &Path, findings: &HashSet<Finding>) -> Result<()> {
+        let mut file = File::create(path).with_context(|| format!("failed to create baseline {}", path.display()))?;
+        report::write_report(&mut file, OutputFormat::Json, findings, &ReportOptions::default())
+    }
+
+    pub fn contains(&self, finding: &Finding) -> bool {
+        self.known.contains(&key(finding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::SafeCounterpart;
+
+    fn method(owner: &str, line: usize) -> Finding {
+        let mut finding = Finding::for_test("get_unchecked", FindingKind::Method, SafeCounterpart::Missing);
+        finding.owner = Some(owner.to_string());
+        finding.span.line = line;
+        finding
+    }
+
+    #[test]
+    fn same_method_on_another_type_is_new() {
+        let baseline = Baseline { known: [key(&method("Vec", 10))].into() };
+        assert!(baseline.contains(&method("Vec", 10)));
+        assert!(!baseline.contains(&method("Slice", 10)));
+    }
+
+    #[test]
+    fn call_of_a_known_function_is_new() {
+        let baseline = Baseline { known: [key(&method("Vec", 10))].into() };
+        assert!(!baseline.contains(&Finding { kind: FindingKind::Call, ..method("Vec", 10) }));
+    }
+
+    #[test]
+    fn moved_or_changed_finding_is_known() {
+        let baseline = Baseline { known: [key(&method("Vec", 10))].into() };
+        let moved = Finding { safe_counterpart: SafeCounterpart::Safe("get".to_string()), ..method("Vec", 42) };
+        assert!(baseline.contains(&moved));
+    }
+}
@@ -0,0 +1,31 @@
+use std::env;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+
+// 与本程序一起安装的扫描程序，即这个包的主二进制文件
+const SCANNER: &str = env!("CARGO_PKG_NAME");
+
+/// `cargo scan-unchecked [ARGS]`: runs the scanner with `ARGS` on the Cargo
+/// workspace containing the current directory, as with `--workspace`.
+fn main() -> ExitCode {
+    // cargo 调用子命令时把子命令名作为第一个参数传入，直接运行时没有
+    let mut args: Vec<OsString> = env::args_os().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "scan-unchecked") {
+        args.remove(0);
+    }
+
+    // 优先使用同一目录下的扫描程序，找不到时从 PATH 中找
+    let scanner = env::current_exe()
+        .ok()
+        .map(|exe| exe.with_file_name(format!("{SCANNER}{}", env::consts::EXE_SUFFIX)))
+        .filter(|path| path.is_file())
+        .unwrap_or_else(|| PathBuf::from(SCANNER));
+    match Command::new(&scanner).args(&args).env("SCAN_UNCHECKED_WORKSPACE", "true").status() {
+        Ok(status) => status.code().map_or(ExitCode::FAILURE, |code| ExitCode::from(code as u8)),
+        Err(error) => {
+            eprintln!("error: failed to run {}: {error}", scanner.display());
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+use regex::Regex;
+use syn::visit::{self, Visit};
+use syn::{Expr, ExprCall, ExprMethodCall, ImplItemFn, ItemFn, ItemImpl, ItemMod, ItemTrait, TraitItemFn};
+
+use crate::config::Settings;
+use crate::files::{self, SourceFile};
+use crate::scan;
+
+/// Calls from any function in the scanned files to functions whose name
+/// matches the pattern, as `(caller, callee)` pairs.
+///
+/// Callees are only known by name, since resolving the receiver type would
+/// need type checking. Callers that match the pattern themselves are named
+/// the same way so chains of unchecked calls link up; other callers use their
+/// module path and impl type, e.g. `core::slice::Iter::next`.
+pub struct CallGraph {
+    pub edges: BTreeSet<(String, String)>,
+}
+
+pub fn build(settings: &Settings, files: &[SourceFile]) -> CallGraph {
+    let edges = Mutex::new(BTreeSet::new());
+    files.par_iter().for_each(|file| {
+        // 无法读取或解析的文件在扫描时已经报告过，这里直接跳过
+        let Some(parsed_file) = parse(file) else {
+            return;
+        };
+        let mut visitor = CallVisitor {
+            pattern: &settings.pattern,
+            module_path: files::crate_module_path(settings, file),
+            impl_type: None,
+            callers: Vec::new(),
+            edges: BTreeSet::new(),
+        };
+        visitor.visit_file(&parsed_file);
+        edges.lock().unwrap().extend(visitor.edges);
+    });
+    CallGraph { edges: edges.into_inner().unwrap() }
+}
+
+fn parse(file: &SourceFile) -> Option<syn::File> {
+    syn::parse_file(&file.read().ok()?).ok()
+}
+
+struct CallVisitor<'a> {
+    pattern: &'a Regex,
+    module_path: String,
+    // 当前所在 impl 块的类型名或 trait 名，trait 中默认实现里的调用归到 trait
+    impl_type: Option<String>,
+    // 嵌套函数时栈顶是最内层的函数
+    callers: Vec<String>,
+    edges: BTreeSet<(String, String)>,
+}
+
+impl CallVisitor<'_> {
+    fn caller_name(&self, name: String) -> String {
+        if self.pattern.is_match(&name) {
+            return name;
+        }
+        match &self.impl_type {
+            Some(impl_type) => format!("{}::{}::{}", self.module_path, impl_type, name),
+            None => format!("{}::{}", self.module_path, name),
+        }
+    }
+
+    fn add_call(&mut self, callee: String) {
+        if !self.pattern.is_match(&callee) {
+            return;
+        }
+        // 不在函数中的调用（例如 const 初始化）归到模块
+        let caller = self.callers.last().cloned().unwrap_or_else(|| self.module_path.clone());
+        self.edges.insert((caller, callee));
+    }
+
+    fn with_caller(&mut self, name: String, visit: impl FnOnce(&mut Self)) {
+        let caller = self.caller_name(name);
+        self.callers.push(caller);
+        visit(self);
+        self.callers.pop();
+    }
+}
+
+impl<'ast> Visit<'ast> for CallVisitor<'_> {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        self.with_caller(node.sig.ident.to_string(), |visitor| visit::visit_item_fn(visitor, node));
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.with_caller(node.sig.ident.to_string(), |visitor| visit::visit_impl_item_fn(visitor, node));
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast TraitItemFn) {
+        self.with_caller(node.sig.ident.to_string(), |visitor| visit::visit_trait_item_fn(visitor, node));
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        let outer = self.impl_type.replace(scan::type_name(&node.self_ty));
+        visit::visit_item_impl(self, node);
+        self.impl_type = outer;
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        let outer = self.impl_type.replace(node.ident.to_string());
+        visit::visit_item_trait(self, node);
+        self.impl_type = outer;
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast ItemMod) {
+        let outer = self.module_path.clone();
+        self.module_path = format!("{}::{}", outer, node.ident);
+        visit::visit_item_mod(self, node);
+        self.module_path = outer;
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let Expr::Path(path) = &*node.func {
+            if let Some(segment) = path.path.segments.last() {
+                self.add_call(segment.ident.to_string());
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        self.add_call(node.method.to_string());
+        visit::visit_expr_method_call(self, node);
+    }
+}
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+
+/// What kind of check an unchecked function skips, guessed from the words in
+/// its name and the type it belongs to.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    // 下标、切片范围等越界检查
+    Indexing,
+    // 溢出、除以零等
+    Arithmetic,
+    // 字符串内容的有效性，例如 str 必须是 UTF-8；旧版本的报告中叫 encoding
+    #[serde(alias = "encoding")]
+    StringValidity,
+    // NonZero、char、枚举等值的有效性
+    Construction,
+    // 假定 MaybeUninit 等内存已经初始化
+    Uninitialized,
+    // 裸指针、对齐和内存布局
+    Pointer,
+    // 锁和线程间的同步
+    Synchronization,
+    #[default]
+    Other,
+}
+
+impl Category {
+    // 与 json 中的取值一致
+    pub fn name(self) -> &'static str {
+        match self {
+            Category::Indexing => "indexing",
+            Category::Arithmetic => "arithmetic",
+            Category::StringValidity => "string_validity",
+            Category::Construction => "construction",
+            Category::Uninitialized => "uninitialized",
+            Category::Pointer => "pointer",
+            Category::Synchronization => "synchronization",
+            Category::Other => "other",
+        }
+    }
+
+    /// What the caller of a function in this category has to guarantee, for
+    /// reports that explain the categories.
+    pub fn invariant(self) -> &'static str {
+        match self {
+            Category::Indexing => "the index or range must be in bounds",
+            Category::Arithmetic => "the operation must not overflow, divide by zero or shift out of range",
+            Category::StringValidity => "the bytes must be valid UTF-8, or valid for the string type (e.g. nul-terminated for `CStr`)",
+            Category::Construction => "the value must be valid for its type, e.g. non-zero for `NonZero` or the right variant",
+            Category::Uninitialized => "the memory must already be initialized",
+            Category::Pointer => "the pointer must be non-null, aligned and valid for the access",
+            Category::Synchronization => "the caller must rule out data races, e.g. by holding the lock",
+            Category::Other => "see the function's `# Safety` documentation",
+        }
+    }
+}
+
+// 名字中按下划线分开的单词，按下面的顺序第一个匹配的类别胜出；
+// 未初始化内存最容易出错，最先判断
+const UNINITIALIZED_WORDS: [&str; 3] = ["uninit", "uninitialized", "zeroed"];
+const SYNCHRONIZATION_WORDS: [&str; 11] =
+    ["lock", "unlock", "locked", "sync", "unsynchronized", "atomic", "mutex", "rwlock", "park", "notify", "wait"];
+const STRING_WORDS: [&str; 11] =
+    ["utf8", "utf16", "ascii", "char", "chars", "str", "string", "encode", "decode", "boundary", "wtf8"];
+const ARITHMETIC_WORDS: [&str; 16] = [
+    "add", "sub", "mul", "div", "rem", "neg", "shl", "shr", "pow", "exact", "abs", "int", "sqrt", "ilog", "bitor", "funnel",
+];
+const INDEXING_WORDS: [&str; 17] = [
+    "get", "index", "slice", "split", "swap", "chunks", "range", "insert", "remove", "push", "pop", "first", "last",
+    "array", "at", "next", "back",
+];
+// unwrap 和 downcast 假定了枚举的成员或值的具体类型
+const CONSTRUCTION_WORDS: [&str; 10] =
+    ["new", "from", "assume", "init", "transmute", "nonzero", "into", "variant", "unwrap", "downcast"];
+const POINTER_WORDS: [&str; 13] =
+    ["ptr", "offset", "layout", "align", "read", "write", "copy", "deref", "raw", "size", "alloc", "dealloc", "cast"];
+
+// 名字中看不出类别时按所属类型判断
+const POINTER_TYPES: [&str; 3] = ["NonNull", "Layout", "Alignment"];
+const SYNCHRONIZATION_TYPES: [&str; 6] = ["Mutex", "RwLock", "Condvar", "Once", "OnceLock", "Barrier"];
+// 字符串类型的方法，例如 str::get_unchecked 和 String::as_mut_vec，不管名字都可能破坏字符串的有效性
+const STRING_TYPES: [&str; 8] = ["str", "String", "Wtf8", "Wtf8Buf", "CStr", "CString", "OsStr", "OsString"];
+const CHAR_TYPES: [&str; 2] = ["char", "AsciiChar"];
+const ARITHMETIC_TYPES: [&str; 14] =
+    ["u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize", "f32", "f64"];
+const INDEXING_TYPES: [&str; 3] = ["Vec", "VecDeque", "ArrayVec"];
+
+/// Guesses the category of an unchecked function from its name and, for
+/// methods, the name of its type or trait as recorded in `owner`. Methods
+/// of raw pointer types are always [`Category::Pointer`], since `add` or
+/// `get_unchecked` on a pointer skip pointer checks rather than arithmetic
+/// or bounds checks. Likewise methods of string types are always
+/// [`Category::StringValidity`].
+pub fn classify(name: &str, owner: Option<&str>) -> Category {
+    let owner = owner.unwrap_or_default();
+    if owner.starts_with("*const") || owner.starts_with("*mut") || POINTER_TYPES.contains(&owner) {
+        return Category::Pointer;
+    }
+    if owner.starts_with("Atomic") || SYNCHRONIZATION_TYPES.contains(&owner) {
+        return Category::Synchronization;
+    }
+    if STRING_TYPES.contains(&owner) {
+        return Category::StringValidity;
+    }
+
+    let words: Vec<String> = name.to_ascii_lowercase().split('_').map(str::to_string).collect();
+    let has_word = |list: &[&str]| words.iter().any(|word| list.contains(&word.as_str()));
+    if words.windows(2).any(|pair| pair == ["assume", "init"]) {
+        return Category::Uninitialized;
+    }
+    let by_name = [
+        (&UNINITIALIZED_WORDS[..], Category::Uninitialized),
+        (&SYNCHRONIZATION_WORDS[..], Category::Synchronization),
+        (&STRING_WORDS[..], Category::StringValidity),
+        (&ARITHMETIC_WORDS[..], Category::Arithmetic),
+        (&INDEXING_WORDS[..], Category::Indexing),
+        (&CONSTRUCTION_WORDS[..], Category::Construction),
+        (&POINTER_WORDS[..], Category::Pointer),
+    ];
+    if let Some((_, category)) = by_name.iter().find(|(list, _)| has_word(list)) {
+        return *category;
+    }
+
+    // `int_impl!` 和 `uint_impl!` 宏中生成的是整数类型的方法
+    let integer_macro = owner.ends_with("int_impl!");
+    if owner == "MaybeUninit" {
+        Category::Uninitialized
+    } else if CHAR_TYPES.contains(&owner) {
+        Category::StringValidity
+    } else if ARITHMETIC_TYPES.contains(&owner) || owner.starts_with("NonZero") || integer_macro {
+        Category::Arithmetic
+    } else if owner.starts_with('[') || INDEXING_TYPES.contains(&owner) {
+        Category::Indexing
+    } else {
+        Category::Other
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+
+use syn::punctuated::Punctuated;
+use syn::{Expr, Lit, Meta, Token};
+
+/// Names of the features a finding's `cfg(..)` attributes, as recorded on
+/// the finding, depend on. `cfg_attr` only changes attributes, not whether
+/// the item exists, so it is ignored.
+pub fn features(cfgs: &[String]) -> Vec<String> {
+    let mut features = Vec::new();
+    for predicate in cfgs.iter().filter_map(|cfg| predicate(cfg)) {
+        collect_features(&predicate, &mut features);
+    }
+    features
+}
+
+/// Whether an item under all of `cfgs` is compiled with exactly `features`
+/// enabled. Conditions on anything other than features (the target, `test`
+/// and so on) are unknown here and never exclude an item.
+pub fn is_active(cfgs: &[String], features: &HashSet<String>) -> bool {
+    cfgs.iter()
+        .filter_map(|cfg| predicate(cfg))
+        .all(|predicate| evaluate(&predicate, features) != Some(false))
+}
+
+/// Whether an item under all of `cfgs` is only compiled for tests, as with
+/// `#[cfg(test)]` or `#[cfg(all(test, unix))]`.
+pub fn is_test_only(cfgs: &[String]) -> bool {
+    cfgs.iter().filter_map(|cfg| predicate(cfg)).any(|predicate| requires_test(&predicate))
+}
+
+// 条件成立是否一定要求 `test`
+fn requires_test(meta: &Meta) -> bool {
+    let path = meta.path();
+    if path.is_ident("test") {
+        matches!(meta, Meta::Path(_))
+    } else if path.is_ident("all") {
+        nested(meta).iter().any(requires_test)
+    } else if path.is_ident("any") {
+        let nested = nested(meta);
+        !nested.is_empty() && nested.iter().all(requires_test)
+    } else {
+        false
+    }
+}
+
+// `cfg(..)` 中的条件；cfg_attr 和无法解析的返回 None
+fn predicate(cfg: &str) -> Option<Meta> {
+    match syn::parse_str::<Meta>(cfg).ok()? {
+        Meta::List(list) if list.path.is_ident("cfg") => list.parse_args().ok(),
+        _ => None,
+    }
+}
+
+// all、any、not 中的条件
+fn nested(meta: &Meta) -> Vec<Meta> {
+    match meta {
+        Meta::List(list) => list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            .map(|nested| nested.into_iter().collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+// `feature = "名字"` 中的名字
+fn feature_name(meta: &Meta) -> Option<String> {
+    match meta {
+        Meta::NameValue(name_value) if name_value.path.is_ident("feature") => match &name_value.value {
+            Expr::Lit(expr) => match &expr.lit {
+                Lit::Str(name) => Some(name.value()),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn collect_features(meta: &Meta, features: &mut Vec<String>) {
+    match feature_name(meta) {
+        Some(name) if !features.contains(&name) => features.push(name),
+        Some(_) => {}
+        None => {
+            for meta in nested(meta) {
+                collect_features(&meta, features);
+            }
+        }
+    }
+}
+
+// 三值逻辑：None 表示取决于 feature 以外的条件
+fn evaluate(meta: &Meta, features: &HashSet<String>) -> Option<bool> {
+    if let Some(name) = feature_name(meta) {
+        return Some(features.contains(&name));
+    }
+    let path = meta.path();
+    let values: Vec<Option<bool>> = nested(meta).iter().map(|meta| evaluate(meta, features)).collect();
+    if path.is_ident("all") {
+        if values.contains(&Some(false)) {
+            Some(false)
+        } else if values.iter().all(|value| *value == Some(true)) {
+            Some(true)
+        } else {
+            None
+        }
+    } else if path.is_ident("any") {
+        if values.contains(&Some(true)) {
+            Some(true)
+        } else if values.iter().all(|value| *value == Some(false)) {
+            Some(false)
+        } else {
+            None
+        }
+    } else if path.is_ident("not") {
+        values.first().copied().flatten().map(|value| !value)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,266 @@
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use syn::visit::{self, Visit};
+use syn::{BinOp, Block, Expr, ExprBinary, ExprCall, ExprIf, ExprMatch, ExprMethodCall, ExprPath, ExprTry, ExprUnsafe, ItemFn, Local, Macro};
+
+// 会 panic 的宏；debug_assert! 和 assert_unsafe_precondition! 在发布版本中不检查，不算
+const PANIC_MACROS: [&str; 5] = ["assert", "assert_eq", "assert_ne", "panic", "unreachable"];
+const PANIC_METHODS: [&str; 2] = ["unwrap", "expect"];
+
+/// What kind of validation a piece of a function body performs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckKind {
+    // 条件中有大小比较的 if 或 match 守卫，例如 `if index < self.len()`
+    Bounds,
+    // 其他的 if 条件、match 和 let-else，例如 `if let Some(..) = ..`
+    Branch,
+    // 返回 None 或 Err 的路径，包括 `?`
+    Failure,
+    // assert!、panic!、unwrap() 等
+    Panic,
+}
+
+impl CheckKind {
+    // 与 json 中的取值一致
+    pub fn name(self) -> &'static str {
+        match self {
+            CheckKind::Bounds => "bounds",
+            CheckKind::Branch => "branch",
+            CheckKind::Failure => "failure",
+            CheckKind::Panic => "panic",
+        }
+    }
+}
+
+/// A validating construct in a function body, with its source as written
+/// (the condition for branches).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Check {
+    pub kind: CheckKind,
+    pub code: String,
+}
+
+impl Check {
+    // 例如 `bounds: index < self.len()`
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.kind.name(), self.code)
+    }
+}
+
+/// The checks in a function body, and whether any of them validates before
+/// the body reaches its first `unsafe` block.
+#[derive(Default)]
+pub struct BodyChecks {
+    // 按源码中的顺序，每个检查只记一次
+    pub checks: Vec<Check>,
+    // 第一个 unsafe 块之前有没有返回 None/Err 或 panic 的检查；没有 unsafe 块时为 None
+    pub validates: Option<bool>,
+}
+
+/// The checks in `block`. Functions defined inside the block are not part
+/// of it.
+pub fn collect(block: &Block) -> BodyChecks {
+    let mut collector = CheckCollector::default();
+    collector.visit_block(block);
+    BodyChecks { checks: collector.checks, validates: collector.reached_unsafe.then_some(collector.validates) }
+}
+
+/// The checks of `safe` that `unchecked` does not have, which is the
+/// validation the safe version adds to the unchecked one.
+pub fn added(safe: &[Check], unchecked: &[Check]) -> Vec<Check> {
+    safe.iter().filter(|check| !unchecked.contains(check)).cloned().collect()
+}
+
+#[derive(Default)]
+struct CheckCollector {
+    checks: Vec<Check>,
+    reached_unsafe: bool,
+    validates: bool,
+    // 遇到的返回 None/Err 或 panic 的次数，包括重复的
+    failures: usize,
+}
+
+impl CheckCollector {
+    fn add(&mut self, kind: CheckKind, code: String) {
+        if matches!(kind, CheckKind::Failure | CheckKind::Panic) {
+            self.failures += 1;
+            self.validates |= !self.reached_unsafe;
+        }
+        let check = Check { kind, code };
+        if !self.checks.contains(&check) {
+            self.checks.push(check);
+        }
+    }
+
+    fn add_condition(&mut self, condition: &Expr) {
+        let kind = if compares(condition) { CheckKind::Bounds } else { CheckKind::Branch };
+        self.add(kind, render(condition));
+    }
+
+    // 在 unsafe 代码之前开始的分支中，任何一支返回 None/Err 或 panic 都算验证，
+    // 例如 `if i < len { unsafe { .. } } else { None }`
+    fn visit_branch(&mut self, visit: impl FnOnce(&mut Self)) {
+        let before_unsafe = !self.reached_unsafe;
+        let failures = self.failures;
+        visit(self);
+        self.validates |= before_unsafe && self.failures > failures;
+    }
+}
+
+impl<'ast> Visit<'ast> for CheckCollector {
+    fn visit_expr_if(&mut self, node: &'ast ExprIf) {
+        self.add_condition(&node.cond);
+        self.visit_branch(|this| visit::visit_expr_if(this, node));
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast ExprMatch) {
+        self.add(CheckKind::Branch, format!("match {}", render(&node.expr)));
+        for arm in &node.arms {
+            if let Some((_, guard)) = &arm.guard {
+                self.add_condition(guard);
+            }
+        }
+        self.visit_branch(|this| visit::visit_expr_match(this, node));
+    }
+
+    fn visit_local(&mut self, node: &'ast Local) {
+        if node.init.as_ref().is_some_and(|init| init.diverge.is_some()) {
+            self.add(CheckKind::Branch, render_local(node));
+            self.visit_branch(|this| visit::visit_local(this, node));
+        } else {
+            visit::visit_local(self, node);
+        }
+    }
+
+    fn visit_expr_path(&mut self, node: &'ast ExprPath) {
+        if node.qself.is_none() && node.path.is_ident("None") {
+            self.add(CheckKind::Failure, "None".to_string());
+        }
+        visit::visit_expr_path(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let Expr::Path(path) = &*node.func {
+            let name = path.path.segments.last().map(|segment| segment.ident.to_string()).unwrap_or_default();
+            if path.path.is_ident("Err") {
+                self.add(CheckKind::Failure, render(&Expr::Call(node.clone())));
+            } else if name.contains("panic") || name.ends_with("_fail") {
+                // 标准库用 `slice_error_fail`、`panic_bounds_check` 等函数报告错误
+                self.add(CheckKind::Panic, render(&Expr::Call(node.clone())));
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_try(&mut self, node: &'ast ExprTry) {
+        self.add(CheckKind::Failure, render(&Expr::Try(node.clone())));
+        visit::visit_expr_try(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        if PANIC_METHODS.contains(&node.method.to_string().as_str()) {
+            self.add(CheckKind::Panic, render(&Expr::MethodCall(node.clone())));
+        }
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        let is_panic = node.path.segments.last().is_some_and(|segment| PANIC_MACROS.contains(&segment.ident.to_string().as_str()));
+        if is_panic {
+            self.add(CheckKind::Panic, render_macro(node));
+        }
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast ExprUnsafe) {
+        self.reached_unsafe = true;
+        visit::visit_expr_unsafe(self, node);
+    }
+
+    fn visit_item_fn(&mut self, _node: &'ast ItemFn) {}
+}
+
+// 条件中是否有大小比较，不算闭包中的
+fn compares(condition: &Expr) -> bool {
+    struct Comparisons(bool);
+    impl<'ast> Visit<'ast> for Comparisons {
+        fn visit_expr_binary(&mut self, node: &'ast ExprBinary) {
+            self.0 |= matches!(node.op, BinOp::Lt(_) | BinOp::Le(_) | BinOp::Gt(_) | BinOp::Ge(_));
+            visit::visit_expr_binary(self, node);
+        }
+
+        fn visit_expr_closure(&mut self, _node: &'ast syn::ExprClosure) {}
+    }
+    let mut comparisons = Comparisons(false);
+    comparisons.visit_expr(condition);
+    comparisons.0
+}
+
+// 用 prettyplease 格式化，它会把较长的表达式拆成多行，这里再合并成一行。
+// 直接构造语法树而不是重新解析，`if let` 的条件不能单独解析
+fn render(expr: &Expr) -> String {
+    render_stmt(syn::Stmt::Expr(expr.clone(), Some(Default::default())))
+}
+
+fn render_macro(mac: &Macro) -> String {
+    render_stmt(syn::Stmt::Macro(syn::StmtMacro { attrs: Vec::new(), mac: mac.clone(), semi_token: Some(Default::default()) }))
+}
+
+// let-else 只取 `let 模式 = 表达式`，不带 else 块
+fn render_local(local: &Local) -> String {
+    let mut local = local.clone();
+    if let Some(init) = &mut local.init {
+        init.diverge = None;
+    }
+    render_stmt(syn::Stmt::Local(local))
+}
+
+fn render_stmt(stmt: syn::Stmt) -> String {
+    if has_verbatim(&stmt) {
+        return stmt.to_token_stream().to_string().trim_end_matches(';').trim_end().to_string();
+    }
+    let mut item: ItemFn = syn::parse_quote!(fn f() {});
+    item.block.stmts.push(stmt);
+    let file = syn::File { shebang: None, attrs: Vec::new(), items: vec![syn::Item::Fn(item)] };
+    let rendered = prettyplease::unparse(&file);
+    let body: Vec<&str> = rendered.lines().map(str::trim).collect();
+    // 去掉第一行的 `fn f() {` 和最后一行的 `}`
+    let body = &body[1..body.len().saturating_sub(1).max(1)];
+    let mut code = String::new();
+    for line in body {
+        // 拆行时最后一项后面有逗号
+        if line.starts_with([')', ']', '}']) && code.ends_with(',') {
+            code.pop();
+        }
+        // 拆行时链式调用以 `.` 开头，参数列表以 `(` 结尾
+        if !code.is_empty() && !line.starts_with('.') && !line.starts_with(')') && !code.ends_with('(') {
+            code.push(' ');
+        }
+        code.push_str(line);
+    }
+    code.trim_end_matches(';').to_string()
+}
+
+// prettyplease 不能格式化 syn 没有解析的语法，例如 `&raw const x`
+fn has_verbatim(stmt: &syn::Stmt) -> bool {
+    struct Verbatim(bool);
+    impl<'ast> Visit<'ast> for Verbatim {
+        fn visit_expr(&mut self, node: &'ast Expr) {
+            self.0 |= matches!(node, Expr::Verbatim(_));
+            visit::visit_expr(self, node);
+        }
+
+        fn visit_type(&mut self, node: &'ast syn::Type) {
+            self.0 |= matches!(node, syn::Type::Verbatim(_));
+            visit::visit_type(self, node);
+        }
+
+        fn visit_pat(&mut self, node: &'ast syn::Pat) {
+            self.0 |= matches!(node, syn::Pat::Verbatim(_));
+            visit::visit_pat(self, node);
+        }
+    }
+    let mut verbatim = Verbatim(false);
+    verbatim.visit_stmt(stmt);
+    verbatim.0
+}
@@ -0,0 +1,439 @@
+use std::path::PathBuf;
+
+use clap::{ArgAction, Args, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::badge::BadgeFormat;
+use crate::naming::Builtin;
+use crate::profile::Profile;
+use crate::registry::CrateSpec;
+use crate::scan::{Detect, VisibilityFilter};
+use crate::report::{OutputFormat, SortOrder};
+
+/// Scan a Rust source tree for `unchecked` functions and check whether each
+/// one has a safe counterpart.
+///
+/// Without a subcommand, the arguments are those of `scan`.
+/// Most scan options can also be set through the `SCAN_UNCHECKED_*`
+/// environment variables listed below, which take precedence over the config
+/// file.
+#[derive(Parser, Debug)]
+#[command(name = "scan_unchecked", version, about, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub scan: ScanArgs,
+
+    /// Print more progress information (-v for each file, -vv for each match)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Only print errors
+    #[arg(short, long, conflicts_with = "verbose", global = true)]
+    pub quiet: bool,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Scan source files and write a report of the findings
+    Scan(Box<ScanArgs>),
+    /// Download a crate from crates.io and scan it
+    Crate(Box<CrateArgs>),
+    /// Clone a git repository and scan it
+    Git(Box<GitArgs>),
+    /// Render a JSON report from an earlier scan in another format
+    Report(ReportArgs),
+    /// Show the findings added and removed between two JSON reports
+    Diff(DiffArgs),
+    /// Scan two versions of a crate and show how their unchecked functions
+    /// differ
+    Compare(Box<CompareArgs>),
+    /// Print aggregate counts for a JSON report
+    Stats(StatsArgs),
+    /// Write a README badge with the share of unchecked functions that have a
+    /// safe counterpart
+    Badge(BadgeArgs),
+    /// Browse a JSON report interactively and mark findings as reviewed
+    Tui(TuiArgs),
+    /// Print a shell completion script to standard output
+    Completions(CompletionsArgs),
+}
+
+#[derive(Args, Debug, Clone, Default)]
+pub struct ScanArgs {
+    /// Directories to scan; findings from all of them are merged into one
+    /// report. A `.crate`, `.tar.gz`, `.tgz` or `.zip` archive is scanned
+    /// without unpacking it
+    #[arg(value_name = "ROOT", default_value = ".", env = "SCAN_UNCHECKED_ROOT")]
+    pub roots: Vec<PathBuf>,
+
+    /// Scan the .rs files listed in FILE, one per line, instead of walking
+    /// ROOT (`-` reads the list from standard input)
+    #[arg(long, value_name = "FILE")]
+    pub files_from: Option<PathBuf>,
+
+    /// Keep running and rescan whenever .rs files under ROOT change, printing
+    /// the findings that were added or removed
+    #[arg(short, long, conflicts_with = "files_from")]
+    pub watch: bool,
+
+    /// Expand macros with `cargo expand` (or rustc) and scan the expanded
+    /// source of the package in each ROOT, instead of the files on disk
+    #[arg(long, conflicts_with_all = ["watch", "files_from"])]
+    pub expand: bool,
+
+    /// Scan the source directories of all members of the Cargo workspace
+    /// containing each ROOT, as listed by `cargo metadata`, and report the
+    /// package of each finding. This is the default when run as
+    /// `cargo scan-unchecked`
+    #[arg(long, conflicts_with_all = ["expand", "files_from"], env = "SCAN_UNCHECKED_WORKSPACE")]
+    pub workspace: bool,
+
+    /// Like --workspace, but also scan every dependency in `Cargo.lock`
+    /// from its downloaded source, to audit the whole dependency tree
+    #[arg(long, conflicts_with_all = ["expand", "files_from"])]
+    pub with_deps: bool,
+
+    /// Read the functions from FILE, the JSON output of `cargo +nightly
+    /// rustdoc -- -Z unstable-options --output-format json`, instead of
+    /// parsing the source under ROOT. This sees functions generated by
+    /// macros and reports canonical item paths, but only the name-based
+    /// --detect rules apply and counterparts are matched by name only
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["watch", "expand", "files_from", "workspace", "with_deps"]
+    )]
+    pub rustdoc_json: Option<PathBuf>,
+
+    /// Print the files that would be scanned, after applying the filters,
+    /// without parsing them
+    #[arg(long, conflicts_with = "watch")]
+    pub dry_run: bool,
+
+    /// What to look for and which files to scan
+    #[command(flatten)]
+    pub options: ScanOptions,
+
+    /// List the functions in the scanned tree that call each function found,
+    /// with their file and line
+    #[arg(long)]
+    pub callers: bool,
+
+    /// Write the report to FILE instead of `safe_version_results.txt`
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Write the report to standard output
+    #[arg(long, conflicts_with = "output")]
+    pub stdout: bool,
+
+    /// Report format [default: table]
+    #[arg(short, long, value_enum, env = "SCAN_UNCHECKED_FORMAT")]
+    pub format: Option<OutputFormat>,
+
+    /// Render the report with a Tera template FILE instead of a built-in
+    /// format; takes precedence over --format
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// Print rustc-style warnings with the source line of each finding and a
+    /// suggested safe signature; short for `--format diagnostics --stdout`
+    #[arg(long, conflicts_with_all = ["format", "output", "template"])]
+    pub diagnostics: bool,
+
+    /// Include the source line each function is defined on, and N more lines
+    /// after it, in json and html reports
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "0")]
+    pub snippet: Option<usize>,
+
+    /// Group the table and markdown reports by crate and module, with
+    /// subtotals for each group
+    #[arg(long)]
+    pub group: bool,
+
+    /// Order of the findings in the report [default: path]
+    #[arg(long, value_enum, env = "SCAN_UNCHECKED_SORT")]
+    pub sort: Option<SortOrder>,
+
+    /// Only report findings that are not recorded in the baseline FILE
+    #[arg(long, value_name = "FILE", env = "SCAN_UNCHECKED_BASELINE")]
+    pub baseline: Option<PathBuf>,
+
+    /// Record all current findings in the --baseline file instead of
+    /// filtering by it
+    #[arg(long)]
+    pub update_baseline: bool,
+
+    /// Exit with a nonzero status if any unchecked function lacks a safe
+    /// counterpart (same as `--max-findings 0`)
+    #[arg(long, conflicts_with = "max_findings")]
+    pub fail_on_findings: bool,
+
+    /// Exit with a nonzero status if more than N unchecked functions lack a
+    /// safe counterpart
+    #[arg(long, value_name = "N")]
+    pub max_findings: Option<usize>,
+
+    /// Exit with a nonzero status if any unchecked function has no safe
+    /// version at all, ignoring those whose safe version is itself unsafe
+    #[arg(long)]
+    pub fail_on_missing_safe: bool,
+}
+
+/// The scan options that decide what is reported and which files are read,
+/// shared by `scan` and `compare`.
+#[derive(Args, Debug, Clone, Default)]
+pub struct ScanOptions {
+    /// Config file to use instead of the `scan_unchecked.toml` found in the
+    /// first ROOT
+    #[arg(long, value_name = "FILE", env = "SCAN_UNCHECKED_CONFIG")]
+    pub config: Option<PathBuf>,
+
+    /// Start from a built-in set of settings, which the config file and the
+    /// other flags then override
+    #[arg(long, value_enum, env = "SCAN_UNCHECKED_PROFILE")]
+    pub profile: Option<Profile>,
+
+    /// Do not descend more than N directory levels below ROOT (1 scans only
+    /// the files directly in ROOT)
+    #[arg(long, value_name = "N", env = "SCAN_UNCHECKED_MAX_DEPTH")]
+    pub max_depth: Option<usize>,
+
+    /// Also scan hidden files and files listed in .gitignore or .ignore
+    #[arg(long)]
+    pub no_ignore: bool,
+
+    /// Report functions whose name matches REGEX [default: unchecked]
+    #[arg(short, long, value_name = "REGEX", env = "SCAN_UNCHECKED_PATTERN")]
+    pub pattern: Option<String>,
+
+    /// Also report functions following a built-in naming convention and look
+    /// for their safe counterparts accordingly, comma-separated or repeated.
+    /// Together with --pattern these replace the config file's `conventions`
+    #[arg(long = "convention", value_name = "NAME", value_enum, value_delimiter = ',')]
+    pub conventions: Vec<Builtin>,
+
+    /// Detection rules to run, comma-separated or repeated [default: name]
+    #[arg(
+        long,
+        visible_alias = "rules",
+        value_name = "RULE",
+        value_enum,
+        value_delimiter = ',',
+        env = "SCAN_UNCHECKED_DETECT"
+    )]
+    pub detect: Vec<Detect>,
+
+    /// Only report definitions with this visibility, e.g. `pub` to check a
+    /// library's public API [default: all]
+    #[arg(long, value_enum, env = "SCAN_UNCHECKED_VISIBILITY")]
+    pub visibility: Option<VisibilityFilter>,
+
+    /// Only report items compiled with exactly these Cargo features enabled,
+    /// comma-separated; pass an empty list for no features. Other `cfg`
+    /// conditions are not evaluated
+    #[arg(long, value_name = "FEATURE", value_delimiter = ',', env = "SCAN_UNCHECKED_FEATURES")]
+    pub features: Option<Vec<String>>,
+
+    /// Only report `const fn` definitions
+    #[arg(long)]
+    pub only_const: bool,
+
+    /// Only report `async fn` definitions
+    #[arg(long)]
+    pub only_async: bool,
+
+    /// Leave out test code: items under `#[cfg(test)]`, `#[test]` functions
+    /// and files under tests/ or benches/. On by default; with
+    /// `--skip-tests=false` test code is reported with a `test` context
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    pub skip_tests: Option<bool>,
+
+    /// Only scan files matching GLOB (repeatable, added to the config file's
+    /// `include` list)
+    #[arg(short, long = "include", value_name = "GLOB")]
+    pub includes: Vec<String>,
+
+    /// Skip files and directories matching GLOB (repeatable, added to the
+    /// config file's `exclude` list)
+    #[arg(short, long = "exclude", value_name = "GLOB")]
+    pub excludes: Vec<String>,
+
+    /// Number of worker threads [default: number of logical CPUs]
+    #[arg(
+        short,
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        env = "SCAN_UNCHECKED_JOBS"
+    )]
+    pub jobs: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+#[command(mut_arg("roots", |arg| arg.hide(true)))]
+pub struct CrateArgs {
+    /// Crate to scan, as NAME or NAME@VERSION; without a version the latest
+    /// stable release is used, and a partial version like 1.2 means the
+    /// latest 1.2.x
+    #[arg(value_name = "CRATE")]
+    pub spec: CrateSpec,
+
+    /// Unpack the crate into DIR and keep it there, instead of a temporary
+    /// directory that is removed after the scan
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// Scan options; the unpacked crate is scanned in place of ROOT
+    #[command(flatten)]
+    pub scan: ScanArgs,
+}
+
+#[derive(Args, Debug)]
+#[command(mut_arg("roots", |arg| arg.hide(true)))]
+pub struct GitArgs {
+    /// URL of the repository, as accepted by `git clone`
+    #[arg(value_name = "URL")]
+    pub url: String,
+
+    /// Scan only this directory of the repository, e.g. `library`; only it
+    /// is checked out
+    #[arg(long, value_name = "PATH")]
+    pub path: Option<PathBuf>,
+
+    /// Branch or tag to clone instead of the default branch
+    #[arg(long, value_name = "REF")]
+    pub branch: Option<String>,
+
+    /// Clone into DIR and keep it there, instead of a temporary directory
+    /// that is removed after the scan; if DIR already is a checkout, it is
+    /// scanned as it is
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+
+    /// Scan options; the checked out PATH is scanned in place of ROOT
+    #[command(flatten)]
+    pub scan: ScanArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct ReportArgs {
+    /// JSON report written by `scan --format json`, or a baseline file
+    #[arg(value_name = "FILE")]
+    pub input: PathBuf,
+
+    /// Report format [default: table]
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// Render the report with a Tera template FILE instead of a built-in
+    /// format; takes precedence over --format
+    #[arg(long, value_name = "FILE")]
+    pub template: Option<PathBuf>,
+
+    /// Include the source line each function is defined on, and N more lines
+    /// after it, in json and html reports
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "0")]
+    pub snippet: Option<usize>,
+
+    /// Group the table and markdown reports by crate and module, with
+    /// subtotals for each group
+    #[arg(long)]
+    pub group: bool,
+
+    /// Order of the findings in the report [default: path]
+    #[arg(long, value_enum)]
+    pub sort: Option<SortOrder>,
+
+    /// Write the report to FILE instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// JSON report of the earlier scan
+    #[arg(value_name = "OLD")]
+    pub old: PathBuf,
+
+    /// JSON report of the later scan
+    #[arg(value_name = "NEW")]
+    pub new: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct CompareArgs {
+    /// Earlier version: a directory, an archive, or NAME@VERSION to download
+    /// from crates.io
+    #[arg(value_name = "OLD")]
+    pub old: String,
+
+    /// Later version, in the same forms as OLD
+    #[arg(value_name = "NEW")]
+    pub new: String,
+
+    /// Print the changes as a Markdown changelog instead of one line each
+    #[arg(long)]
+    pub markdown: bool,
+
+    /// Scan options; OLD and NEW are scanned with them as ROOT
+    #[command(flatten)]
+    pub options: ScanOptions,
+}
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    /// JSON report written by `scan --format json`, or a baseline file
+    #[arg(value_name = "FILE")]
+    pub input: PathBuf,
+}
+
+#[derive(Args, Debug)]
+pub struct BadgeArgs {
+    /// JSON report written by `scan --format json`, or a baseline file
+    #[arg(value_name = "FILE")]
+    pub input: PathBuf,
+
+    /// Badge format
+    #[arg(short, long, value_enum, default_value_t)]
+    pub format: BadgeFormat,
+
+    /// Write the badge to FILE instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct TuiArgs {
+    /// JSON report written by `scan --format json`, or a baseline file
+    #[arg(value_name = "FILE")]
+    pub input: PathBuf,
+
+    /// Where to keep the list of reviewed findings [default: FILE with
+    /// `.reviewed.json` appended]
+    #[arg(long, value_name = "FILE")]
+    pub reviewed: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct CompletionsArgs {
+    /// Shell to generate the completion script for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+impl Cli {
+    pub fn log_level(&self) -> log::LevelFilter {
+        if self.quiet {
+            return log::LevelFilter::Error;
+        }
+        match self.verbose {
+            0 => log::LevelFilter::Info,
+            1 => log::LevelFilter::Debug,
+            _ => log::LevelFilter::Trace,
+        }
+    }
+}
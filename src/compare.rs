@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::cli::{CompareArgs, ScanArgs, ScanOptions};
+use crate::config::Settings;
+use crate::diff;
+use crate::finding::Finding;
+use crate::registry::{self, CrateSpec};
+use crate::{files, scan, workspace};
+
+// 比较的一方：名称和要扫描的目录或压缩包
+struct Version {
+    label: String,
+    root: PathBuf,
+}
+
+/// Scans the two versions in `args` and prints the unchecked functions added,
+/// removed, and whose safe counterpart changed between them, like `diff`
+/// does for two reports. Versions on crates.io are downloaded into a
+/// temporary directory that is removed afterwards.
+pub fn run(args: &CompareArgs) -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let old = prepare(&args.old, temp_dir.path())?;
+    let new = prepare(&args.new, temp_dir.path())?;
+
+    let old_settings = settings(&args.options, &old.root)?;
+    let new_settings = settings(&args.options, &new.root)?;
+    old_settings.init_thread_pool()?;
+    let changes = diff::compare(&findings(&old_settings)?, &findings(&new_settings)?);
+
+    if args.markdown {
+        diff::print_changelog(&changes, &old.label, &new.label);
+    } else {
+        diff::print_changes(&changes);
+    }
+    log::info!(
+        "{} to {}: {} findings added, {} removed, {} changed",
+        old.label,
+        new.label,
+        changes.added.len(),
+        changes.removed.len(),
+        changes.changed.len()
+    );
+    Ok(())
+}
+
+// 已有的路径直接扫描，否则按 NAME@VERSION 从 crates.io 下载
+fn prepare(version: &str, dir: &Path) -> Result<Version> {
+    let path = Path::new(version);
+    if path.exists() {
+        return Ok(Version { label: version.to_string(), root: path.to_path_buf() });
+    }
+    let spec: CrateSpec = version
+        .parse()
+        .ok()
+        .with_context(|| format!("`{version}` is neither an existing path nor NAME@VERSION"))?;
+    let spec = registry::resolve(&spec)?;
+    Ok(Version { label: spec.to_string(), root: registry::download(&spec, dir)? })
+}
+
+// 包按 --workspace 扫描，模块路径以 crate 名开头，而不是随版本变化的目录名
+fn settings(options: &ScanOptions, root: &Path) -> Result<Settings> {
+    let workspace = root.is_dir() && workspace::is_readable(root);
+    Settings::resolve(&ScanArgs { roots: vec![root.to_path_buf()], workspace, options: options.clone(), ..ScanArgs::default() })
+}
+
+fn findings(settings: &Settings) -> Result<HashSet<Finding>> {
+    let files = files::collect_files(settings)?;
+    Ok(scan::scan(settings, &files)?.findings)
+}
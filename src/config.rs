@@ -0,0 +1,311 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::archive;
+use crate::cli::ScanArgs;
+use crate::filter::PathFilter;
+use crate::naming::{self, Convention, ConventionSpec};
+use crate::policy::FailPolicy;
+use crate::profile::Profile;
+use crate::report::{OutputFormat, ReportOptions, SortOrder};
+use crate::scan::{Detect, VisibilityFilter};
+use crate::workspace::{self, Member};
+
+// 在扫描根目录下按顺序查找的配置文件名
+pub const CONFIG_FILE_NAMES: [&str; 2] = ["scan_unchecked.toml", ".scan-unchecked.toml"];
+
+pub const DEFAULT_PATTERN: &str = "unchecked";
+pub const DEFAULT_OUTPUT: &str = "safe_version_results.txt";
+
+/// Contents of a `scan_unchecked.toml` file. Every key is optional; anything
+/// left out falls back to the built-in default.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub profile: Option<Profile>,
+    pub pattern: Option<String>,
+    pub conventions: Vec<ConventionSpec>,
+    #[serde(alias = "rules")]
+    pub detect: Vec<Detect>,
+    pub visibility: Option<VisibilityFilter>,
+    pub features: Option<Vec<String>>,
+    pub only_const: Option<bool>,
+    pub only_async: Option<bool>,
+    pub skip_tests: Option<bool>,
+    pub callers: Option<bool>,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub max_depth: Option<usize>,
+    pub no_ignore: Option<bool>,
+    pub output: Option<PathBuf>,
+    pub format: Option<OutputFormat>,
+    pub template: Option<PathBuf>,
+    pub snippet: Option<usize>,
+    pub group: Option<bool>,
+    pub sort: Option<SortOrder>,
+    pub jobs: Option<usize>,
+    pub baseline: Option<PathBuf>,
+    pub fail_on_findings: Option<bool>,
+    pub max_findings: Option<usize>,
+    pub fail_on_missing_safe: Option<bool>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    // 本配置中没有设置的项使用 base 的值，include/exclude 列表则合并；
+    // 开关也是 Option，配置文件可以用 false 关掉 profile 打开的开关
+    fn or(self, base: Config) -> Config {
+        Config {
+            profile: self.profile,
+            pattern: self.pattern.or(base.pattern),
+            conventions: if self.conventions.is_empty() { base.conventions } else { self.conventions },
+            detect: if self.detect.is_empty() { base.detect } else { self.detect },
+            visibility: self.visibility.or(base.visibility),
+            features: self.features.or(base.features),
+            only_const: self.only_const.or(base.only_const),
+            only_async: self.only_async.or(base.only_async),
+            skip_tests: self.skip_tests.or(base.skip_tests),
+            callers: self.callers.or(base.callers),
+            include: [base.include, self.include].concat(),
+            exclude: [base.exclude, self.exclude].concat(),
+            max_depth: self.max_depth.or(base.max_depth),
+            no_ignore: self.no_ignore.or(base.no_ignore),
+            output: self.output.or(base.output),
+            format: self.format.or(base.format),
+            template: self.template.or(base.template),
+            snippet: self.snippet.or(base.snippet),
+            group: self.group.or(base.group),
+            sort: self.sort.or(base.sort),
+            jobs: self.jobs.or(base.jobs),
+            baseline: self.baseline.or(base.baseline),
+            fail_on_findings: self.fail_on_findings.or(base.fail_on_findings),
+            max_findings: self.max_findings.or(base.max_findings),
+            fail_on_missing_safe: self.fail_on_missing_safe.or(base.fail_on_missing_safe),
+        }
+    }
+
+    // 在根目录中查找配置文件，找不到时返回 None
+    pub fn discover(root: &Path) -> Option<PathBuf> {
+        CONFIG_FILE_NAMES.iter().map(|name| root.join(name)).find(|path| path.is_file())
+    }
+}
+
+/// Where the report is written. A path of `-` means standard output.
+pub enum Output {
+    File(PathBuf),
+    Stdout,
+}
+
+impl Output {
+    pub fn from_path(path: PathBuf) -> Self {
+        if path.as_os_str() == "-" {
+            Output::Stdout
+        } else {
+            Output::File(path)
+        }
+    }
+}
+
+/// Final scan settings after merging the config file with CLI flags.
+pub struct Settings {
+    pub roots: Vec<PathBuf>,
+    // --workspace 时各个扫描根目录所属的包，按 SourceFile::root 查找
+    pub members: HashMap<String, Member>,
+    pub files_from: Option<PathBuf>,
+    pub watch: bool,
+    pub expand: bool,
+    pub rustdoc_json: Option<PathBuf>,
+    pub dry_run: bool,
+    // 匹配任一命名约定的名字
+    pub pattern: Regex,
+    pub conventions: Vec<Convention>,
+    pub detect: Vec<Detect>,
+    pub visibility: VisibilityFilter,
+    // 没有设置时不按 feature 过滤
+    pub features: Option<HashSet<String>>,
+    pub only_const: bool,
+    pub only_async: bool,
+    pub skip_tests: bool,
+    pub callers: bool,
+    pub filter: PathFilter,
+    pub max_depth: Option<usize>,
+    pub no_ignore: bool,
+    pub output: Output,
+    pub format: OutputFormat,
+    pub report_options: ReportOptions,
+    // 0 表示使用 rayon 的默认线程数
+    pub jobs: usize,
+    pub baseline: Option<PathBuf>,
+    pub update_baseline: bool,
+    pub fail_policy: FailPolicy,
+}
+
+impl Settings {
+    pub fn resolve(args: &ScanArgs) -> Result<Self> {
+        let config_path = match &args.options.config {
+            Some(path) => Some(path.clone()),
+            None => Config::discover(&args.roots[0]),
+        };
+        let config = match config_path {
+            Some(path) => Config::load(&path)?,
+            None => Config::default(),
+        };
+        let config = match args.options.profile.or(config.profile) {
+            Some(profile) => config.or(profile.config()),
+            None => config,
+        };
+
+        let mut include = config.include;
+        include.extend(args.options.includes.iter().cloned());
+        let mut exclude = config.exclude;
+        exclude.extend(args.options.excludes.iter().cloned());
+
+        // 命令行上的 --convention 和 --pattern 一起替换配置文件中的命名约定
+        let mut conventions = args.options.conventions
+            .iter()
+            .map(|builtin| Convention::new(&ConventionSpec::Builtin(*builtin)))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(pattern) = &args.options.pattern {
+            conventions.push(Convention::stripping(pattern)?);
+        }
+        if conventions.is_empty() {
+            conventions = config.conventions.iter().map(Convention::new).collect::<Result<_>>()?;
+            if let Some(pattern) = &config.pattern {
+                conventions.push(Convention::stripping(pattern)?);
+            }
+        }
+        if conventions.is_empty() {
+            conventions.push(Convention::stripping(DEFAULT_PATTERN)?);
+        }
+        let pattern = naming::combined(&conventions);
+
+        // --fail-on-findings 等价于 --max-findings 0
+        let max_findings = if args.fail_on_findings {
+            Some(0)
+        } else {
+            args.max_findings
+                .or(config.max_findings)
+                .or(config.fail_on_findings.unwrap_or(false).then_some(0))
+        };
+
+        let workspace = args.workspace || args.with_deps;
+        let members = if workspace {
+            workspace::members(&args.roots, args.with_deps)?
+        } else {
+            // 压缩包中的 crate 按其中的 Cargo.toml 归属到包
+            let archives = args.roots.iter().filter(|root| archive::is_archive(root));
+            archives.filter_map(|root| archive::member(root).transpose()).collect::<Result<_>>()?
+        };
+        // --diagnostics 不用配置文件中的模板
+        let template = if args.diagnostics { None } else { args.template.clone().or(config.template) };
+        let baseline = args.baseline.clone().or(config.baseline);
+        if args.update_baseline && baseline.is_none() {
+            bail!("--update-baseline needs a baseline file from --baseline or the config file");
+        }
+
+        Ok(Settings {
+            roots: if workspace { members.iter().map(|member| member.root.clone()).collect() } else { args.roots.clone() },
+            members: members.into_iter().map(|member| (member.root.display().to_string(), member)).collect(),
+            files_from: args.files_from.clone(),
+            watch: args.watch,
+            expand: args.expand,
+            rustdoc_json: args.rustdoc_json.clone(),
+            dry_run: args.dry_run,
+            pattern,
+            conventions: conventions.clone(),
+            detect: if !args.options.detect.is_empty() {
+                args.options.detect.clone()
+            } else if !config.detect.is_empty() {
+                config.detect
+            } else {
+                vec![Detect::Name]
+            },
+            visibility: args.options.visibility.or(config.visibility).unwrap_or_default(),
+            // `--features ''` 表示不启用任何 feature
+            features: args.options.features.clone().or(config.features).map(|features| {
+                features.into_iter().filter(|feature| !feature.is_empty()).collect()
+            }),
+            only_const: args.options.only_const || config.only_const.unwrap_or(false),
+            only_async: args.options.only_async || config.only_async.unwrap_or(false),
+            skip_tests: args.options.skip_tests.or(config.skip_tests).unwrap_or(true),
+            callers: args.callers || config.callers.unwrap_or(false),
+            filter: PathFilter::new(&include, &exclude)?,
+            max_depth: args.options.max_depth.or(config.max_depth),
+            no_ignore: args.options.no_ignore || config.no_ignore.unwrap_or(false),
+            output: if args.stdout || args.diagnostics {
+                Output::Stdout
+            } else {
+                let path = args.output.clone().or(config.output);
+                Output::from_path(path.unwrap_or_else(|| PathBuf::from(DEFAULT_OUTPUT)))
+            },
+            // 设置了模板时忽略格式
+            format: match template {
+                _ if args.diagnostics => OutputFormat::Diagnostics,
+                Some(_) => OutputFormat::default(),
+                None => args.format.or(config.format).unwrap_or_default(),
+            },
+            report_options: ReportOptions {
+                group: args.group || config.group.unwrap_or(false),
+                sort: args.sort.or(config.sort).unwrap_or_default(),
+                template,
+                snippet: args.snippet.or(config.snippet),
+                conventions,
+                ..ReportOptions::default()
+            },
+            jobs: args.options.jobs.map(|jobs| jobs as usize).or(config.jobs).unwrap_or(0),
+            baseline,
+            update_baseline: args.update_baseline,
+            fail_policy: FailPolicy {
+                max_findings,
+                fail_on_missing_safe: args.fail_on_missing_safe || config.fail_on_missing_safe.unwrap_or(false),
+            },
+        })
+    }
+
+    /// Sets up the global thread pool that files are parsed on, with `jobs`
+    /// threads. Only the first call in a process has an effect.
+    pub fn init_thread_pool(&self) -> Result<()> {
+        // jobs 为 0 时由 rayon 按 CPU 数决定线程数
+        rayon::ThreadPoolBuilder::new().num_threads(self.jobs).build_global()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_file_can_turn_off_a_profile_switch() {
+        let config: Config = toml::from_str("fail_on_findings = false").unwrap();
+        let merged = config.or(Profile::Strict.config());
+        assert_eq!(merged.fail_on_findings, Some(false));
+        assert_eq!(Config::default().or(Profile::Strict.config()).fail_on_findings, Some(true));
+    }
+
+    #[test]
+    fn config_file_values_take_precedence() {
+        let config: Config = toml::from_str("pattern = \"_raw$\"\nformat = \"json\"\nexclude = [\"vendor\"]").unwrap();
+        let merged = config.or(Profile::StdAudit.config());
+        assert_eq!(merged.pattern.as_deref(), Some("_raw$"));
+        assert_eq!(merged.format, Some(OutputFormat::Json));
+        assert_eq!(merged.skip_tests, None);
+    }
+
+    #[test]
+    fn include_and_exclude_lists_are_merged() {
+        let config: Config = toml::from_str("exclude = [\"vendor\"]").unwrap();
+        let merged = config.or(Profile::Strict.config());
+        assert_eq!(merged.exclude, ["target", "vendor"]);
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::Result;
+
+use crate::cli::DiffArgs;
+use crate::finding::{Finding, FindingKind};
+use crate::report;
+
+/// Differences between two sets of findings.
+///
+/// Findings are matched by module path, type, name and kind rather than by
+/// file and position, so a function that only moved (within a file, or to
+/// another checkout of the same tree) is neither added nor removed. A matched
+/// finding whose safe counterpart differs is reported as changed.
+#[derive(Default)]
+pub struct Changes {
+    pub removed: Vec<Finding>,
+    pub added: Vec<Finding>,
+    // (旧, 新)
+    pub changed: Vec<(Finding, Finding)>,
+}
+
+/// Prints the findings of `args.new` that are not in `args.old` (`+`), the
+/// other way round (`-`), and those whose safe counterpart changed (`~`).
+pub fn run(args: &DiffArgs) -> Result<()> {
+    let old = report::read_json(&args.old)?;
+    let new = report::read_json(&args.new)?;
+
+    let changes = compare(&old, &new);
+    print_changes(&changes);
+
+    log::info!(
+        "{} findings added, {} removed, {} changed",
+        changes.added.len(),
+        changes.removed.len(),
+        changes.changed.len()
+    );
+    Ok(())
+}
+
+// 模块路径、所属类型或 trait、函数名和种类
+type Key<'a> = (&'a str, Option<&'a str>, &'a str, FindingKind);
+
+pub fn compare(old: &HashSet<Finding>, new: &HashSet<Finding>) -> Changes {
+    // 每个键对应旧的和新的两组结果
+    let mut matched: BTreeMap<Key, [Vec<&Finding>; 2]> = BTreeMap::new();
+    for finding in old {
+        matched.entry(key(finding)).or_default()[0].push(finding);
+    }
+    for finding in new {
+        matched.entry(key(finding)).or_default()[1].push(finding);
+    }
+
+    let mut changes = Changes::default();
+    for [mut old, mut new] in matched.into_values() {
+        // 同一模块中有多个同名函数时（例如同一类型的多个 impl 中的方法），按位置依次配对
+        old.sort_by_key(|finding| position(finding));
+        new.sort_by_key(|finding| position(finding));
+        let pairs = old.len().min(new.len());
+        for (old, new) in old.iter().zip(&new) {
+            if old.safe_counterpart != new.safe_counterpart {
+                changes.changed.push(((*old).clone(), (*new).clone()));
+            }
+        }
+        changes.removed.extend(old[pairs..].iter().map(|finding| (*finding).clone()));
+        changes.added.extend(new[pairs..].iter().map(|finding| (*finding).clone()));
+    }
+    changes
+}
+
+fn key(finding: &Finding) -> Key<'_> {
+    (finding.module_path.as_str(), finding.owner.as_deref(), finding.function.as_str(), finding.kind)
+}
+
+fn position(finding: &Finding) -> (&str, usize, usize) {
+    (finding.file.as_str(), finding.span.line, finding.span.column)
+}
+
+// watch 模式也用同样的格式输出变化
+pub fn print_changes(changes: &Changes) {
+    let mut removed: Vec<&Finding> = changes.removed.iter().collect();
+    let mut added: Vec<&Finding> = changes.added.iter().collect();
+    let mut changed: Vec<&(Finding, Finding)> = changes.changed.iter().collect();
+    removed.sort_by_key(|finding| position(finding));
+    added.sort_by_key(|finding| position(finding));
+    changed.sort_by_key(|(_, new)| position(new));
+
+    for finding in removed {
+        println!("- {}: {} ({})", finding.location(), finding.qualified_name(), finding.safe_counterpart);
+    }
+    for finding in added {
+        println!("+ {}: {} ({})", finding.location(), finding.qualified_name(), finding.safe_counterpart);
+    }
+    for (old, new) in changed {
+        println!("~ {}: {} ({} -> {})", new.location(), new.qualified_name(), old.safe_counterpart, new.safe_counterpart);
+    }
+}
+
+/// Prints `changes` as a Markdown changelog of the unchecked API from `old`
+/// to `new`, with the functions of each section ordered by name.
+pub fn print_changelog(changes: &Changes, old: &str, new: &str) {
+    println!("## Unchecked API changes from {old} to {new}");
+    if changes.added.is_empty() && changes.removed.is_empty() && changes.changed.is_empty() {
+        println!("\nNo changes.");
+        return;
+    }
+
+    let mut added: Vec<&Finding> = changes.added.iter().collect();
+    let mut removed: Vec<&Finding> = changes.removed.iter().collect();
+    let mut changed: Vec<&(Finding, Finding)> = changes.changed.iter().collect();
+    added.sort_by_key(|finding| finding.qualified_name());
+    removed.sort_by_key(|finding| finding.qualified_name());
+    changed.sort_by_key(|(_, new)| new.qualified_name());
+
+    for (title, findings) in [("Added", added), ("Removed", removed)] {
+        if !findings.is_empty() {
+            println!("\n### {title}\n");
+            for finding in findings {
+                println!("- `{}` ({})", finding.qualified_name(), finding.safe_counterpart);
+            }
+        }
+    }
+    if !changed.is_empty() {
+        println!("\n### Safe counterpart changed\n");
+        for (old, new) in changed {
+            println!("- `{}`: {} -> {}", new.qualified_name(), old.safe_counterpart, new.safe_counterpart);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::SafeCounterpart;
+
+    fn method(owner: &str, line: usize, safe_counterpart: SafeCounterpart) -> Finding {
+        let mut finding = Finding::for_test("get_unchecked", FindingKind::Method, safe_counterpart);
+        finding.owner = Some(owner.to_string());
+        finding.span.line = line;
+        finding
+    }
+
+    #[test]
+    fn moved_finding_is_unchanged() {
+        let old = HashSet::from([method("Vec", 10, SafeCounterpart::Missing)]);
+        let new = HashSet::from([method("Vec", 20, SafeCounterpart::Missing)]);
+        let changes = compare(&old, &new);
+        assert!(changes.added.is_empty() && changes.removed.is_empty() && changes.changed.is_empty());
+    }
+
+    #[test]
+    fn same_method_on_another_type_is_a_different_finding() {
+        let old = HashSet::from([method("Vec", 10, SafeCounterpart::Missing)]);
+        let new = HashSet::from([method("Slice", 10, SafeCounterpart::Missing)]);
+        let changes = compare(&old, &new);
+        assert_eq!(changes.removed[0].owner.as_deref(), Some("Vec"));
+        assert_eq!(changes.added[0].owner.as_deref(), Some("Slice"));
+    }
+
+    #[test]
+    fn new_counterpart_is_a_change() {
+        let old = HashSet::from([method("Vec", 10, SafeCounterpart::Missing)]);
+        let new = HashSet::from([method("Vec", 10, SafeCounterpart::Safe("get".to_string()))]);
+        let changes = compare(&old, &new);
+        assert_eq!(changes.changed.len(), 1);
+        assert!(changes.added.is_empty() && changes.removed.is_empty());
+    }
+
+    #[test]
+    fn findings_of_another_kind_do_not_match() {
+        let old = HashSet::from([method("Vec", 10, SafeCounterpart::Missing)]);
+        let new = HashSet::from([Finding { kind: FindingKind::SafeUnchecked, ..method("Vec", 10, SafeCounterpart::Missing) }]);
+        let changes = compare(&old, &new);
+        assert_eq!((changes.added.len(), changes.removed.len()), (1, 1));
+    }
+}
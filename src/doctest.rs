@@ -0,0 +1,157 @@
+use regex::Regex;
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{Attribute, Expr, ExprCall, ExprLit, ExprMethodCall, ExprUnsafe, Lit, Macro, Meta, MetaNameValue, Token};
+
+// 代码块的语言标记中只有这些时才是 Rust 代码，和 rustdoc 一样没有标记的也是
+const RUST_ATTRIBUTES: [&str; 11] = [
+    "rust",
+    "ignore",
+    "should_panic",
+    "no_run",
+    "test_harness",
+    "standalone_crate",
+    "edition2015",
+    "edition2018",
+    "edition2021",
+    "edition2024",
+    "allow_fail",
+];
+
+/// A Rust code block in an item's doc comment that uses unchecked APIs.
+pub struct DocExample {
+    // 开始代码块的那一行在源文件中的行号
+    pub line: usize,
+    // 示例中调用的名字匹配的函数，按出现的顺序
+    pub unchecked_calls: Vec<String>,
+    pub unsafe_blocks: usize,
+    // 示例是否写明了它依赖的前提：`// SAFETY:` 注释，或者第一个 unsafe 之前的 assert!
+    pub shows_preconditions: bool,
+}
+
+/// The Rust examples in the doc comments among `attrs` that call a function
+/// matching `pattern` or contain an `unsafe` block. Examples marked
+/// `compile_fail` and examples that do not parse are skipped.
+pub fn examples(attrs: &[Attribute], pattern: &Regex) -> Vec<DocExample> {
+    let mut examples = Vec::new();
+    // 当前代码块的开始行和其中的代码行，不在 Rust 代码块中时为 None
+    let mut block: Option<(usize, Vec<String>)> = None;
+    let mut in_other_block = false;
+    for (line, text) in doc_lines(attrs) {
+        let trimmed = text.trim();
+        if let Some(info) = trimmed.strip_prefix("```").or_else(|| trimmed.strip_prefix("~~~")) {
+            if let Some((start, code)) = block.take() {
+                examples.extend(scan(start, &code.join("\n"), pattern));
+            } else if in_other_block {
+                in_other_block = false;
+            } else if is_rust(info) {
+                block = Some((line, Vec::new()));
+            } else {
+                in_other_block = true;
+            }
+        } else if let Some((_, code)) = &mut block {
+            code.push(unhide(trimmed).to_string());
+        }
+    }
+    examples
+}
+
+// 文档注释的每一行和它在源文件中的行号；`/** */` 中的多行按在注释中的位置推算
+fn doc_lines(attrs: &[Attribute]) -> Vec<(usize, String)> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(MetaNameValue { value: Expr::Lit(ExprLit { lit: Lit::Str(doc), .. }), .. }) => {
+                Some((attr.pound_token.span.start().line, doc.value()))
+            }
+            _ => None,
+        })
+        .flat_map(|(start, doc)| {
+            doc.lines().enumerate().map(|(offset, text)| (start + offset, text.to_string())).collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn is_rust(info: &str) -> bool {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|token| !token.is_empty())
+        .all(|token| RUST_ATTRIBUTES.contains(&token) || token.starts_with("ignore-"))
+}
+
+// 以 `# ` 开头的行在文档中隐藏，但仍然是示例的一部分
+fn unhide(line: &str) -> &str {
+    if line == "#" {
+        ""
+    } else {
+        line.strip_prefix("# ").unwrap_or(line)
+    }
+}
+
+fn scan(line: usize, code: &str, pattern: &Regex) -> Option<DocExample> {
+    // 没有 main 的示例由 rustdoc 包在 main 中
+    let file = syn::parse_file(code).or_else(|_| syn::parse_file(&format!("fn main() {{\n{code}\n}}"))).ok()?;
+    let mut visitor = ExampleVisitor { pattern, unchecked_calls: Vec::new(), unsafe_blocks: 0 };
+    visitor.visit_file(&file);
+    if visitor.unchecked_calls.is_empty() && visitor.unsafe_blocks == 0 {
+        return None;
+    }
+    Some(DocExample {
+        line,
+        unchecked_calls: visitor.unchecked_calls,
+        unsafe_blocks: visitor.unsafe_blocks,
+        shows_preconditions: shows_preconditions(code),
+    })
+}
+
+fn shows_preconditions(code: &str) -> bool {
+    let lines: Vec<&str> = code.lines().map(str::trim).collect();
+    let safety_comment = lines.iter().any(|line| line.starts_with("//") && line.to_lowercase().contains("safety"));
+    let first_unsafe = lines.iter().position(|line| line.contains("unsafe")).unwrap_or(lines.len());
+    let asserted = lines[..first_unsafe].iter().any(|line| line.contains("assert!(") || line.contains("assert_eq!("));
+    safety_comment || asserted
+}
+
+struct ExampleVisitor<'a> {
+    pattern: &'a Regex,
+    unchecked_calls: Vec<String>,
+    unsafe_blocks: usize,
+}
+
+impl ExampleVisitor<'_> {
+    fn check_call(&mut self, name: String) {
+        if self.pattern.is_match(&name) {
+            self.unchecked_calls.push(name);
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for ExampleVisitor<'_> {
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let Expr::Path(path) = &*node.func {
+            if let Some(segment) = path.path.segments.last() {
+                self.check_call(segment.ident.to_string());
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        self.check_call(node.method.to_string());
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast ExprUnsafe) {
+        self.unsafe_blocks += 1;
+        visit::visit_expr_unsafe(self, node);
+    }
+
+    // 示例中的不安全调用常常写在 assert_eq! 等宏的参数中
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        if let Ok(args) = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::Settings;
+use crate::files::SourceFile;
+
+/// Expands the macros of the package in each scan root and returns the
+/// expanded sources as the files to scan.
+///
+/// `cargo expand` is used when it is installed; otherwise rustc is asked for
+/// `-Zunpretty=expanded` output of the library target directly. Each
+/// expansion is written to `target/scan_unchecked/expand/<crate>/src/lib.rs`
+/// under the root, so that reported locations point into a file that exists
+/// and module paths start with the crate name.
+pub fn expand_roots(settings: &Settings) -> Result<Vec<SourceFile>> {
+    settings.roots.iter().map(|root| expand(root)).collect()
+}
+
+fn expand(root: &Path) -> Result<SourceFile> {
+    let manifest = root.join("Cargo.toml");
+    let crate_name = package_name(&manifest)?.replace('-', "_");
+    log::info!("Expanding macros in {}", manifest.display());
+
+    let output = match Command::new("cargo").arg("expand").arg("--manifest-path").arg(&manifest).output() {
+        Ok(output) if !is_missing_subcommand(&output) => output,
+        // 没有安装 cargo-expand 时直接让 rustc 输出展开后的代码
+        _ => Command::new("cargo")
+            .args(["rustc", "--quiet", "--profile=check", "--lib", "--manifest-path"])
+            .arg(&manifest)
+            .args(["--", "-Zunpretty=expanded"])
+            .env("RUSTC_BOOTSTRAP", "1")
+            .output()
+            .context("failed to run cargo")?,
+    };
+    if !output.status.success() {
+        bail!(
+            "failed to expand macros in {}:\n{}",
+            manifest.display(),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+    }
+
+    let expand_dir = root.join("target/scan_unchecked/expand");
+    let path: PathBuf = expand_dir.join(&crate_name).join("src/lib.rs");
+    fs::create_dir_all(path.parent().unwrap())?;
+    // 不是 git 仓库时 target 目录不会被忽略，避免之后普通扫描时重复扫描展开的代码
+    fs::write(expand_dir.join(".ignore"), "*\n")?;
+    fs::write(&path, &output.stdout).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(SourceFile {
+        root: root.display().to_string(),
+        path: path.display().to_string(),
+        content: None,
+    })
+}
+
+fn is_missing_subcommand(output: &Output) -> bool {
+    String::from_utf8_lossy(&output.stderr).contains("no such command")
+}
+
+// 只能展开单个包，工作区的根目录需要分别指定各个成员
+fn package_name(manifest: &Path) -> Result<String> {
+    let content = fs::read_to_string(manifest)
+        .with_context(|| format!("--expand needs a Cargo package as ROOT, cannot read {}", manifest.display()))?;
+    let manifest_toml: toml::Table = toml::from_str(&content).with_context(|| format!("invalid manifest {}", manifest.display()))?;
+    match manifest_toml.get("package").and_then(|package| package.get("name")).and_then(|name| name.as_str()) {
+        Some(name) => Ok(name.to_string()),
+        None => bail!("{} has no [package] section; pass the member packages as ROOT instead", manifest.display()),
+    }
+}
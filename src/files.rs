@@ -0,0 +1,265 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use ignore::WalkBuilder;
+
+use crate::archive;
+use crate::config::Settings;
+
+/// A Rust file to scan and the scan root it was found under.
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    pub root: String,
+    pub path: String,
+    // 从压缩包中读出的内容；为 None 时从 path 读取
+    pub content: Option<Arc<str>>,
+}
+
+impl SourceFile {
+    /// The source code of the file.
+    pub fn read(&self) -> io::Result<String> {
+        match &self.content {
+            Some(content) => Ok(content.to_string()),
+            None => fs::read_to_string(&self.path),
+        }
+    }
+}
+
+/// Lists the Rust files to scan, either from `--files-from` or by walking
+/// every scan root. A file reachable from several overlapping roots is only
+/// returned once, for the first root given.
+pub fn collect_files(settings: &Settings) -> Result<Vec<SourceFile>> {
+    let mut files = Vec::new();
+    match &settings.files_from {
+        Some(list) => read_file_list(list, settings, &mut files)?,
+        None => {
+            for root in &settings.roots {
+                if archive::is_archive(root) {
+                    archive::collect_files(root, settings, &mut files)?;
+                } else if root.is_file() {
+                    // 根目录也可以直接是单个文件
+                    files.push(SourceFile {
+                        root: root.display().to_string(),
+                        path: root.display().to_string(),
+                        content: None,
+                    });
+                } else {
+                    process_directory(root, settings, &mut files)?;
+                }
+            }
+        }
+    }
+
+    let mut seen = HashSet::new();
+    files.retain(|file| {
+        let key = fs::canonicalize(&file.path).unwrap_or_else(|_| PathBuf::from(&file.path));
+        seen.insert(key)
+    });
+    Ok(files)
+}
+
+// 收集目录下所有需要扫描的 Rust 文件，默认遵循 .gitignore/.ignore 并跳过隐藏文件
+pub fn process_directory(root: &Path, settings: &Settings, files: &mut Vec<SourceFile>) -> Result<()> {
+    if !root.exists() {
+        bail!("failed to read directory {}: no such file or directory", root.display());
+    }
+
+    let filter = settings.filter.clone();
+    let filter_root = root.to_path_buf();
+    let respect_ignore = !settings.no_ignore;
+    let walker = WalkBuilder::new(root)
+        .standard_filters(respect_ignore)
+        // 不在 git 仓库里时也读取 .gitignore
+        .require_git(false)
+        .max_depth(settings.max_depth)
+        .filter_entry(move |entry| {
+            // 跳过被 exclude 规则命中的文件和目录
+            let relative_path = entry.path().strip_prefix(&filter_root).unwrap_or(entry.path());
+            !filter.is_excluded(relative_path)
+        })
+        .build();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::warn!("{}", err);
+                continue;
+            }
+        };
+
+        let path = entry.path();
+        let relative_path = path.strip_prefix(root).unwrap_or(path);
+        let is_file = entry.file_type().is_some_and(|file_type| file_type.is_file());
+        if is_file
+            && path.extension().is_some_and(|ext| ext == "rs")
+            && settings.filter.is_included(relative_path)
+        {
+            files.push(SourceFile {
+                root: root.display().to_string(),
+                path: path.display().to_string(),
+                content: None,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// 从文件（`-` 表示标准输入）读取以换行分隔的文件列表，
+// 非 .rs 文件和已不存在的文件（例如 git diff 中被删除的文件）会被忽略
+fn read_file_list(list: &Path, settings: &Settings, files: &mut Vec<SourceFile>) -> Result<()> {
+    let reader: Box<dyn BufRead> = if list.as_os_str() == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        let file = fs::File::open(list).with_context(|| format!("failed to open {}", list.display()))?;
+        Box::new(BufReader::new(file))
+    };
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        // 文件归属于包含它的第一个根目录，都不包含时归属于第一个根目录
+        let path = Path::new(line);
+        let root = settings.roots.iter()
+            .find(|root| path.starts_with(root))
+            .unwrap_or(&settings.roots[0]);
+        let relative_path = path.strip_prefix(root).unwrap_or(path);
+        if path.is_file() && accepts(settings, relative_path) {
+            files.push(SourceFile {
+                root: root.display().to_string(),
+                path: path.display().to_string(),
+                content: None,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// 根目录下的直接子项深度为 1
+fn exceeds_max_depth(settings: &Settings, relative_path: &Path) -> bool {
+    settings.max_depth.is_some_and(|max_depth| relative_path.components().count() > max_depth)
+}
+
+/// Whether a file found outside the directory walk would have been scanned:
+/// it must be a `.rs` file within `--max-depth`, pass the include filter,
+/// and neither it nor any of its parent directories may be excluded.
+pub fn accepts(settings: &Settings, relative_path: &Path) -> bool {
+    relative_path.extension().is_some_and(|ext| ext == "rs")
+        && !exceeds_max_depth(settings, relative_path)
+        && settings.filter.is_included(relative_path)
+        && !relative_path.ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| settings.filter.is_excluded(ancestor))
+}
+
+/// Module path of a scanned file: [`module_path`], but starting with the
+/// crate name from `cargo metadata` when the file is in a workspace member.
+pub fn crate_module_path(settings: &Settings, file: &SourceFile) -> String {
+    let module_path = module_path(Path::new(&file.root), Path::new(&file.path));
+    match settings.members.get(&file.root) {
+        // 目录名不一定是 crate 名，例如 `crates/core` 中的包可能叫 `foo-core`
+        Some(member) => match module_path.split_once("::") {
+            Some((_, modules)) => format!("{}::{}", member.crate_name, modules),
+            None => member.crate_name.clone(),
+        },
+        None => module_path,
+    }
+}
+
+/// Module path of a file, guessed from its location: the directory before
+/// the last `src` component is taken as the crate name and everything after
+/// it as modules, so `library/core/src/slice/iter.rs` is `core::slice::iter`.
+/// When `src` is directly under the root, as in `./src/lib.rs`, the crate is
+/// named after the package in the root's `Cargo.toml` or the root directory.
+/// Files outside any `src` directory use their path relative to the root.
+pub fn module_path(root: &Path, file: &Path) -> String {
+    let components = normal_components(file);
+    let mut segments: Vec<String> = match components.iter().rposition(|&component| component == "src") {
+        Some(src) => {
+            let krate = match src.checked_sub(1) {
+                Some(index) => Some(components[index].to_string()),
+                None => crate_dir_name(root),
+            };
+            krate.map(|krate| krate.replace('-', "_"))
+                .into_iter()
+                .chain(components[src + 1..].iter().map(|module| module.to_string()))
+                .collect()
+        }
+        None => normal_components(file.strip_prefix(root).unwrap_or(file)).into_iter().map(str::to_string).collect(),
+    };
+
+    // lib.rs、main.rs 和 mod.rs 不单独构成模块
+    if let Some(last) = segments.pop() {
+        let stem = last.strip_suffix(".rs").unwrap_or(&last);
+        if !matches!(stem, "lib" | "main" | "mod") || segments.is_empty() {
+            segments.push(stem.to_string());
+        }
+    }
+    segments.join("::")
+}
+
+// 只保留普通的路径组成部分，`.`、`..` 和根目录不算模块
+fn normal_components(path: &Path) -> Vec<&str> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => name.to_str(),
+            _ => None,
+        })
+        .collect()
+}
+
+// 目录的 crate 名：优先取 Cargo.toml 中的包名，否则取规范化后的目录名
+fn crate_dir_name(dir: &Path) -> Option<String> {
+    let package = fs::read_to_string(dir.join("Cargo.toml")).ok()
+        .and_then(|content| toml::from_str::<toml::Table>(&content).ok())
+        .and_then(|manifest| manifest.get("package")?.get("name")?.as_str().map(str::to_string));
+    package.or_else(|| dir.canonicalize().ok()?.file_name()?.to_str().map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module_path_of(root: &str, file: &str) -> String {
+        module_path(Path::new(root), Path::new(file))
+    }
+
+    #[test]
+    fn crate_name_is_the_directory_before_src() {
+        assert_eq!(module_path_of("library", "library/core/src/slice/iter.rs"), "core::slice::iter");
+        assert_eq!(module_path_of(".", "./my-crate/src/lib.rs"), "my_crate");
+    }
+
+    #[test]
+    fn src_under_the_root_takes_the_package_name() {
+        assert_eq!(module_path_of(".", "./src/lib.rs"), "scan_overScopeUnsafe");
+        assert_eq!(module_path_of(".", "./src/report/mod.rs"), "scan_overScopeUnsafe::report");
+    }
+
+    #[test]
+    fn lib_main_and_mod_files_are_not_modules() {
+        assert_eq!(module_path_of("library", "library/core/src/slice/mod.rs"), "core::slice");
+        assert_eq!(module_path_of("app", "app/src/main.rs"), "app");
+    }
+
+    #[test]
+    fn last_src_component_wins() {
+        assert_eq!(module_path_of(".", "src/tools/x/src/a/b.rs"), "x::a::b");
+    }
+
+    #[test]
+    fn files_outside_src_use_their_relative_path() {
+        assert_eq!(module_path_of("root", "root/benches/bench.rs"), "benches::bench");
+        assert_eq!(module_path_of(".", "./benches/bench.rs"), "benches::bench");
+    }
+}
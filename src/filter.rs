@@ -0,0 +1,48 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Decides which paths under the scan root are scanned. Globs are matched
+/// against the path relative to the root; a glob without a `/` matches an
+/// entry of that name at any depth, like in `.gitignore`.
+///
+/// Excludes apply to both files and directories, includes only to files, so
+/// `--include 'core/**'` still descends through every directory to find them.
+#[derive(Clone)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        let include = if include.is_empty() { None } else { Some(build_glob_set(include)?) };
+        Ok(PathFilter { include, exclude: build_glob_set(exclude)? })
+    }
+
+    pub fn is_excluded(&self, relative_path: &Path) -> bool {
+        self.exclude.is_match(relative_path)
+    }
+
+    // 没有 include 规则时所有文件都被包含
+    pub fn is_included(&self, relative_path: &Path) -> bool {
+        self.include.as_ref().is_none_or(|include| include.is_match(relative_path))
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        // `target/` 与 `target` 等价
+        let trimmed = pattern.trim_end_matches('/');
+        let normalized = if trimmed.contains('/') {
+            trimmed.to_string()
+        } else {
+            format!("**/{trimmed}")
+        };
+        let glob = Glob::new(&normalized).with_context(|| format!("invalid glob `{pattern}`"))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
@@ -0,0 +1,445 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::category::Category;
+use crate::checks::Check;
+
+/// Result of looking up the safe version of an unchecked function.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "status", content = "name", rename_all = "snake_case")]
+pub enum SafeCounterpart {
+    Safe(String),
+    // 找到了安全版本，但它在 unsafe 代码之前没有返回 None/Err 或 panic 的分支，看起来什么也没验证
+    Unvalidated(String),
+    // 找到了同名函数，但它本身也是 unsafe 的
+    Unsafe(String),
+    Missing,
+}
+
+impl SafeCounterpart {
+    // 不做验证的安全版本也是安全的，计入覆盖率
+    pub fn is_safe(&self) -> bool {
+        matches!(self, SafeCounterpart::Safe(_) | SafeCounterpart::Unvalidated(_))
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            SafeCounterpart::Safe(name) | SafeCounterpart::Unvalidated(name) | SafeCounterpart::Unsafe(name) => Some(name),
+            SafeCounterpart::Missing => None,
+        }
+    }
+
+    // 用于 csv 等格式中的状态列
+    pub fn status(&self) -> &'static str {
+        match self {
+            SafeCounterpart::Safe(_) => "safe",
+            SafeCounterpart::Unvalidated(_) => "unvalidated",
+            SafeCounterpart::Unsafe(_) => "unsafe",
+            SafeCounterpart::Missing => "missing",
+        }
+    }
+}
+
+impl fmt::Display for SafeCounterpart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SafeCounterpart::Safe(name) => f.write_str(name),
+            SafeCounterpart::Unvalidated(name) => write!(f, "{name}(no validation)"),
+            SafeCounterpart::Unsafe(name) => write!(f, "{name}(unsafe)"),
+            SafeCounterpart::Missing => f.write_str("None"),
+        }
+    }
+}
+
+/// How the signature of a safe counterpart differs from the unchecked one.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureComparison {
+    // 安全版本返回 Option 或 Result
+    Fallible,
+    // 参数和返回值相同，只是去掉了 unsafe，安全版本大概会 panic
+    DropsUnsafe,
+    // 完全相同，包括是否 unsafe，值得检查
+    Identical,
+    // 参数或返回值有其他不同
+    Different,
+}
+
+impl SignatureComparison {
+    // 与 json 中的取值一致
+    pub fn name(self) -> &'static str {
+        match self {
+            SignatureComparison::Fallible => "fallible",
+            SignatureComparison::DropsUnsafe => "drops_unsafe",
+            SignatureComparison::Identical => "identical",
+            SignatureComparison::Different => "different",
+        }
+    }
+}
+
+/// Whether the safe counterpart is implemented by calling the unchecked
+/// function, the usual pattern for a checked wrapper.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CounterpartRelation {
+    // 安全版本的函数体中调用了同名函数
+    Wraps,
+    // 安全版本有函数体，但没有调用
+    Independent,
+    // 没有找到安全版本，或者找到的安全版本没有函数体（trait 中的声明、外部函数）
+    NotFound,
+}
+
+impl CounterpartRelation {
+    // 与 json 中的取值一致
+    pub fn name(self) -> &'static str {
+        match self {
+            CounterpartRelation::Wraps => "wraps",
+            CounterpartRelation::Independent => "independent",
+            CounterpartRelation::NotFound => "not_found",
+        }
+    }
+}
+
+/// Whether an item of the standard library is stable, from its `#[stable]`
+/// or `#[unstable]` attribute or that of the module or trait it is in.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Stability {
+    pub level: StabilityLevel,
+    // 稳定或不稳定的 feature 的名字
+    pub feature: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum StabilityLevel {
+    Stable,
+    Unstable,
+}
+
+impl StabilityLevel {
+    // 与 json 中的取值一致
+    pub fn name(self) -> &'static str {
+        match self {
+            StabilityLevel::Stable => "stable",
+            StabilityLevel::Unstable => "unstable",
+        }
+    }
+}
+
+/// What kind of item an unchecked function was found as.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    Function,
+    // impl 块中的方法或关联函数
+    Method,
+    // trait 中声明的方法，可能有默认实现
+    TraitMethod,
+    // 在 macro_rules! 的定义中按 token 找到的函数，不一定准确
+    MacroGenerated,
+    // extern 块中声明的外部函数
+    Ffi,
+    // 名字匹配、却没有标为 unsafe 的函数定义，由 `--detect safe-unchecked` 报告
+    SafeUnchecked,
+    // 对名字匹配的函数的调用，而不是定义
+    Call,
+    // 对 slice::from_raw_parts、str::from_utf8_unchecked 等的调用
+    RawConstruction,
+    // 对 mem::transmute 或 transmute_copy 的调用
+    Transmute,
+    // 对 core::intrinsics 或 std::intrinsics 中的函数的调用
+    Intrinsic,
+    // unsafe 代码中解引用了（可能是裸指针的）值的函数
+    PtrDeref,
+    // 含有 unsafe 块的函数，记录块的数量
+    UnsafeBlocks,
+    // 调用了 MaybeUninit::assume_init 等的函数，记录调用的次数
+    AssumeInit,
+    // 项的文档中使用了 unchecked 函数或 unsafe 块、却没有写明前提的示例，函数名一栏是项的名字
+    DocExample,
+    // 用 #[no_mangle] 或 #[export_name] 导出的函数
+    Exported,
+    // `unsafe impl`，函数名一栏是 trait 名，所属类型是实现它的类型
+    UnsafeImpl,
+    // union 的定义
+    Union,
+    // 在 unsafe 代码中读取 union 的字段，只按字段名匹配
+    UnionAccess,
+    // static mut 声明
+    StaticMut,
+    // 直接或经由同一 crate 中的其他函数调用了名字匹配的函数的安全 pub 函数
+    SafeCaller,
+    // 含有 unsafe 块的安全 pub 函数，记录块的数量
+    SafeWrapper,
+}
+
+impl FindingKind {
+    pub fn is_call(self) -> bool {
+        matches!(
+            self,
+            FindingKind::Call | FindingKind::RawConstruction | FindingKind::Transmute | FindingKind::Intrinsic | FindingKind::UnionAccess
+        )
+    }
+
+    // 函数定义，包括按函数统计的结果
+    pub fn is_function(self) -> bool {
+        !self.is_call()
+            && !matches!(self, FindingKind::UnsafeImpl | FindingKind::Union | FindingKind::StaticMut | FindingKind::DocExample)
+    }
+
+    // 名字匹配时算作 unchecked 函数、计入总数和覆盖率的定义，其他种类单独统计
+    pub fn is_unchecked_definition(self) -> bool {
+        matches!(
+            self,
+            FindingKind::Function | FindingKind::Method | FindingKind::TraitMethod | FindingKind::Ffi | FindingKind::MacroGenerated
+        )
+    }
+
+    // 与 json 中 kind 字段的取值一致
+    pub fn name(self) -> &'static str {
+        match self {
+            FindingKind::Function => "function",
+            FindingKind::Method => "method",
+            FindingKind::TraitMethod => "trait_method",
+            FindingKind::MacroGenerated => "macro_generated",
+            FindingKind::Ffi => "ffi",
+            FindingKind::SafeUnchecked => "safe_unchecked",
+            FindingKind::Call => "call",
+            FindingKind::RawConstruction => "raw_construction",
+            FindingKind::Transmute => "transmute",
+            FindingKind::Intrinsic => "intrinsic",
+            FindingKind::PtrDeref => "ptr_deref",
+            FindingKind::UnsafeBlocks => "unsafe_blocks",
+            FindingKind::AssumeInit => "assume_init",
+            FindingKind::DocExample => "doc_example",
+            FindingKind::Exported => "exported",
+            FindingKind::UnsafeImpl => "unsafe_impl",
+            FindingKind::Union => "union",
+            FindingKind::UnionAccess => "union_access",
+            FindingKind::StaticMut => "static_mut",
+            FindingKind::SafeCaller => "safe_caller",
+            FindingKind::SafeWrapper => "safe_wrapper",
+        }
+    }
+}
+
+/// Start of the function name in the source file. Lines and columns are both
+/// 1-based.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Whether a finding is in test code: in an item under `#[cfg(test)]`, in a
+/// `#[test]` or `#[bench]` function, or in a file under `tests/` or
+/// `benches/`. Test code is skipped unless `--skip-tests=false` is given.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Context {
+    #[default]
+    Code,
+    Test,
+}
+
+impl Context {
+    // 与 json 中的取值一致
+    pub fn name(self) -> &'static str {
+        match self {
+            Context::Code => "code",
+            Context::Test => "test",
+        }
+    }
+
+    fn is_code(&self) -> bool {
+        *self == Context::Code
+    }
+}
+
+/// A function in the scanned tree that calls a finding's function. Calls
+/// are matched by name, and by type where the call site names it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Caller {
+    pub file: String,
+    pub line: usize,
+    // 调用所在函数的完整路径，例如 `core::slice::Iter::next`
+    pub function: String,
+}
+
+/// The Cargo package a finding is in, known when scanning with
+/// `--workspace` or `--with-deps`, or scanning a `.crate` archive.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub edition: String,
+    // 不是工作区成员，而是用 --with-deps 扫描的依赖
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dependency: bool,
+}
+
+// 与 cargo 的包规格一样写作 `名字@版本`
+impl fmt::Display for Package {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.name, self.version)
+    }
+}
+
+/// One unchecked function and what was found for its safe version.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Finding {
+    // 该文件所属的扫描根目录
+    pub root: String,
+    // 用 --workspace 扫描时所属的包
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<Package>,
+    pub file: String,
+    pub module_path: String,
+    pub function: String,
+    // 函数名是否匹配 pattern，只有 `--detect unsafe-fn` 时才可能为 false
+    #[serde(default = "matches_by_default")]
+    pub name_matches: bool,
+    // 方法所在 impl 的类型或所属的 trait，宏生成的函数则是 `宏名!`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    // impl Trait for Type 中的方法实现的 trait，带泛型参数，例如 `SliceIndex<[T]>`
+    #[serde(rename = "trait", default, skip_serializing_if = "Option::is_none")]
+    pub trait_name: Option<String>,
+    // 函数定义的完整签名，例如 `pub unsafe fn get_unchecked<I>(&self, index: I) -> &I::Output`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    // 函数自身和外层的 mod、impl、trait 等的 `cfg(..)`/`cfg_attr(..)` 属性，从外到内
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cfg: Vec<String>,
+    // cfg 属性中出现的 feature
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+    // 带有 #[deprecated]，不计入安全版本覆盖率
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    // `const fn` 和 `async fn`，只有带签名的函数定义才可能有
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub const_fn: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub async_fn: bool,
+    // 只有标准库中的项才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stability: Option<Stability>,
+    // unsafe 函数的文档中有没有 `# Safety` 一节，其他结果没有这一项
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safety_doc: Option<bool>,
+    // `# Safety` 一节的内容，段落之间空一行
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub safety_section: Option<String>,
+    // 函数体中有没有 debug_assert! 等检查，只有带函数体的定义才有这一项
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debug_checked: Option<bool>,
+    // 与找到的安全版本的签名比较的结果，两者都是能解析签名的函数定义时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counterpart_signature: Option<SignatureComparison>,
+    // 安全版本是否调用了这个函数，按命名规则能推出安全版本的名字时才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counterpart_relation: Option<CounterpartRelation>,
+    // 安全版本的函数体中有、这个函数的函数体中没有的检查，即安全版本多做的验证
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_checks: Vec<Check>,
+    // 调用所在的函数，只有 `--detect calls` 等找到的调用才有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller: Option<String>,
+    pub kind: FindingKind,
+    // 只在测试代码中的结果为 test，旧版本的报告中没有
+    #[serde(default, skip_serializing_if = "Context::is_code")]
+    pub context: Context,
+    // 按名字和所属类型猜测的检查种类，旧版本的报告中没有
+    #[serde(default)]
+    pub category: Category,
+    pub span: Span,
+    // 按函数计数的规则统计到的次数，例如 `--detect ptr-deref` 的解引用次数
+    // 或 `--detect unsafe-metrics` 和 `--detect safe-wrappers` 的 unsafe 块数，文档示例中的 unsafe 块数也记在这里
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    // `--detect propagation` 找到的函数到名字匹配的函数的调用链，
+    // 第一项是函数本身，最后一项是被调用的函数名
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub call_chain: Vec<String>,
+    // `--detect doc-examples` 找到的示例中调用的名字匹配的函数
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub example_calls: Vec<String>,
+    // 用 `--callers` 扫描时，调用这个函数的函数
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub callers: Vec<Caller>,
+    // 用 `pub use` 重新导出这个函数的公开路径，例如 `alloc::str::from_utf8_unchecked`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reexports: Vec<String>,
+    pub safe_counterpart: SafeCounterpart,
+}
+
+// 较早的报告中只有名字匹配的结果
+fn matches_by_default() -> bool {
+    true
+}
+
+impl Finding {
+    // 模块路径的第一段就是 crate 名
+    pub fn crate_name(&self) -> &str {
+        self.module_path.split("::").next().unwrap_or("")
+    }
+
+    /// The function name, prefixed with its type or trait for methods, or
+    /// `<Type as Trait>` for trait implementations. Calls are named after the
+    /// callee and the function they are in, unsafe impls as `Trait for Type`.
+    pub fn name(&self) -> String {
+        if let (FindingKind::UnsafeImpl, Some(owner)) = (self.kind, &self.owner) {
+            return format!("{} for {}", self.function, owner);
+        }
+        if let (Some(owner), Some(trait_name)) = (&self.owner, &self.trait_name) {
+            return format!("<{} as {}>::{}", owner, trait_name, self.function);
+        }
+        match (&self.owner, &self.caller) {
+            (Some(owner), _) => format!("{}::{}", owner, self.function),
+            (None, Some(caller)) => format!("{} in {}", self.function, caller),
+            (None, None) => self.function.clone(),
+        }
+    }
+
+    /// `module::Type::function`, which tells apart methods of the same name
+    /// on different types.
+    pub fn qualified_name(&self) -> String {
+        match (self.kind.is_call(), &self.caller) {
+            (true, Some(caller)) => format!("{} in {}::{}", self.function, self.module_path, caller),
+            (true, None) => format!("{} in {}", self.function, self.module_path),
+            (false, _) => format!("{}::{}", self.module_path, self.name()),
+        }
+    }
+
+    /// Whether this is the definition of a function whose name matches the
+    /// pattern, which is what summaries and fail thresholds count. Other
+    /// kinds of findings are counted separately.
+    pub fn is_unchecked_function(&self) -> bool {
+        self.name_matches && self.kind.is_unchecked_definition()
+    }
+
+    /// `file:line:column`, the form editors and terminals recognize as a link.
+    pub fn location(&self) -> String {
+        format!("{}:{}:{}", self.file, self.span.line, self.span.column)
+    }
+}
+
+#[cfg(test)]
+impl Finding {
+    // 测试用的结果：`krate` 的 lib.rs 中的自由函数，其余各项取 json 中缺省时的值
+    pub fn for_test(function: &str, kind: FindingKind, safe_counterpart: SafeCounterpart) -> Finding {
+        serde_json::from_value(serde_json::json!({
+            "root": ".",
+            "file": "src/lib.rs",
+            "module_path": "krate",
+            "function": function,
+            "kind": kind,
+            "span": { "line": 1, "column": 1 },
+            "safe_counterpart": safe_counterpart,
+        }))
+        .unwrap()
+    }
+}
@@ -0,0 +1,44 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// Makes `dir` a shallow clone of `url` at `branch`, or at the default branch.
+/// With `path`, only that subdirectory is checked out, as a sparse checkout.
+/// A `dir` that already holds a checkout is used as it is, without fetching.
+pub fn checkout(url: &str, branch: Option<&str>, path: Option<&Path>, dir: &Path) -> Result<()> {
+    if dir.join(".git").exists() {
+        log::info!("Using the existing checkout in {}", dir.display());
+        return Ok(());
+    }
+    if dir.read_dir().is_ok_and(|mut entries| entries.next().is_some()) {
+        bail!("{} is neither empty nor a git checkout", dir.display());
+    }
+
+    log::info!("Cloning {} into {}", url, dir.display());
+    let mut clone = Command::new("git");
+    clone.args(["clone", "--quiet", "--depth", "1"]);
+    // 只取需要的目录；不支持过滤的服务器会忽略 --filter，仍然能克隆
+    if path.is_some() {
+        clone.args(["--filter=blob:none", "--sparse"]);
+    }
+    if let Some(branch) = branch {
+        clone.arg("--branch").arg(branch);
+    }
+    run(clone.arg("--").arg(url).arg(dir)).with_context(|| format!("failed to clone {url}"))?;
+    if let Some(path) = path {
+        let mut sparse = Command::new("git");
+        sparse.arg("-C").arg(dir).args(["sparse-checkout", "set", "--"]).arg(path);
+        run(&mut sparse).with_context(|| format!("failed to check out {} of {url}", path.display()))?;
+    }
+    Ok(())
+}
+
+// 运行 git，失败时把它的错误输出放进错误信息
+fn run(command: &mut Command) -> Result<()> {
+    let output = command.output().context("failed to run git")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim_end());
+    }
+    Ok(())
+}
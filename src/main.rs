@@ -1,178 +1,264 @@
 use std::collections::HashSet;
+use std::env;
 use std::fs::{self, File};
-use std::io::Write;
-use std::sync::{Arc, Mutex};
-use anyhow::Result;
-use syn::{ItemFn, ItemImpl, visit::{self, Visit}, parse_file, ImplItem};
-
-struct FunctionVisitor {
-    unchecked_functions: HashSet<(String, String)>, // 存储 (文件路径, 函数名)
-    current_file: String,
-}
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Mutex;
+use anyhow::{bail, Context, Result};
+use clap::{CommandFactory, Parser};
+use tempfile::TempDir;
+
+mod archive;
+mod badge;
+mod baseline;
+mod callgraph;
+mod category;
+mod cfg;
+mod checks;
+mod cli;
+mod compare;
+mod config;
+mod diff;
+mod doctest;
+mod expand;
+mod files;
+mod naming;
+mod filter;
+mod finding;
+mod git;
+mod policy;
+mod profile;
+mod propagation;
+mod registry;
+mod report;
+mod rules;
+mod rustdoc;
+mod scan;
+mod signature;
+mod stats;
+mod tui;
+mod watch;
+mod workspace;
+
+use baseline::Baseline;
+use cli::{Cli, Command, CrateArgs, GitArgs, ReportArgs, ScanArgs};
+use config::{Output, Settings};
+use files::SourceFile;
+use finding::Finding;
+use report::{OutputFormat, ReportOptions};
+use scan::ScanResults;
 
-impl<'ast> Visit<'ast> for FunctionVisitor {
-    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
-        let fn_name = node.sig.ident.to_string();
-        let current_file = self.current_file.clone();
+// 打开报告的输出目标
+fn open_output(output: &Output) -> Result<Box<dyn Write + Send>> {
+    Ok(match output {
+        Output::File(path) => Box::new(File::create(path)?),
+        Output::Stdout => Box::new(io::stdout()),
+    })
+}
 
-        if fn_name.contains("unchecked") {
-            self.unchecked_functions.insert((current_file, fn_name));
+// 将检查结果输出到文件或标准输出
+fn write_output(output: &Output, format: OutputFormat, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    match output {
+        // 数据库文件会追加新的扫描记录，不能先截断
+        Output::File(path) if format == OutputFormat::Sqlite && options.template.is_none() => {
+            report::sqlite::write(path, results)?;
+            log::info!("Safe version results have been added to the database {}", path.display());
+            return Ok(());
+        }
+        _ => {
+            // 只有直接输出到终端时才着色，设置了 NO_COLOR 时不着色
+            let options = ReportOptions {
+                color: matches!(output, Output::Stdout)
+                    && io::stdout().is_terminal()
+                    && env::var_os("NO_COLOR").is_none(),
+                ..options.clone()
+            };
+            report::write_report(&mut open_output(output)?, format, results, &options)?;
         }
+    }
+    if let Output::File(path) = output {
+        log::info!("Safe version results have been written to {}", path.display());
+    }
+    Ok(())
+}
 
-        visit::visit_item_fn(self, node); // 遍历函数的其他部分
+// --dry-run 时只列出将要扫描的文件和每个根目录下的文件数
+fn print_file_list(settings: &Settings, files: &[SourceFile]) {
+    for file in files {
+        println!("{}", file.path);
     }
+    for root in &settings.roots {
+        let root = root.display().to_string();
+        let count = files.iter().filter(|file| file.root == root).count();
+        log::info!("{}: {} files", root, count);
+    }
+    log::info!("{} files would be scanned", files.len());
+}
 
-    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
-        // 遍历 impl 中的所有函数
-        for item in &node.items {
-            if let ImplItem::Fn(item_fn) = item {
-                let method_name = item_fn.sig.ident.to_string();
-                let current_file = self.current_file.clone();
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
+    // 依赖库的日志只显示警告以上，避免 -v 时刷屏
+    env_logger::Builder::new()
+        .filter_level(cli.log_level().min(log::LevelFilter::Warn))
+        .filter_module(module_path!(), cli.log_level())
+        .format_timestamp(None)
+        .format_target(false)
+        .parse_default_env()
+        .init();
 
-                if method_name.contains("unchecked") {
-                    self.unchecked_functions.insert((current_file, method_name));
-                }
-            }
+    match &cli.command {
+        None => run_scan(&cli.scan),
+        Some(Command::Scan(args)) => run_scan(args),
+        Some(Command::Crate(args)) => run_crate(args),
+        Some(Command::Git(args)) => run_git(args),
+        Some(Command::Report(args)) => run_report(args),
+        Some(Command::Diff(args)) => diff::run(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Compare(args)) => compare::run(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Stats(args)) => stats::run(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Badge(args)) => badge::run(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Tui(args)) => tui::run(args).map(|()| ExitCode::SUCCESS),
+        Some(Command::Completions(args)) => {
+            clap_complete::generate(args.shell, &mut Cli::command(), "scan_unchecked", &mut io::stdout());
+            Ok(ExitCode::SUCCESS)
         }
-        visit::visit_item_impl(self, node); // 继续遍历 impl 结构的其他部分
     }
 }
 
-fn process_file(file_path: &str, unchecked_functions: &Arc<Mutex<HashSet<(String, String)>>>) -> Result<()> {
-    let file_content = fs::read_to_string(file_path)?; // 读取文件内容
-    let parsed_file = parse_file(&file_content)?; // 解析 Rust 文件
-
-    // 创建一个函数访问者
-    let mut visitor = FunctionVisitor {
-        unchecked_functions: HashSet::new(),
-        current_file: file_path.to_string(), // 设置当前文件路径
-    };
+fn run_scan(args: &ScanArgs) -> Result<ExitCode> {
+    let settings = Settings::resolve(args)?;
 
-    // 遍历文件中的所有项
-    visitor.visit_file(&parsed_file);
+    settings.init_thread_pool()?;
 
-    // 将找到的 unchecked 函数记录到输出集合中
-    let mut output = unchecked_functions.lock().unwrap();
-    for func in visitor.unchecked_functions {
-        output.insert(func);
+    if settings.watch {
+        watch::run(&settings)?;
+        return Ok(ExitCode::SUCCESS);
     }
 
-    Ok(())
-}
-
-fn process_directory(dir_path: &str, unchecked_functions: &Arc<Mutex<HashSet<(String, String)>>>) -> Result<()> {
-    let paths: Vec<_> = fs::read_dir(dir_path)?
-        .filter_map(|entry| entry.ok())
-        .map(|entry| entry.path())
-        .collect();
-
-    for path in paths {
-        if path.is_dir() {
-            process_directory(path.to_str().unwrap(), unchecked_functions)?; // 递归处理目录
-        } else if let Some(ext) = path.extension() {
-            if ext == "rs" {
-                let path_display = path.display().to_string();
-                println!("Processing file: {}", path_display);
-                process_file(&path_display, unchecked_functions)?; // 处理 Rust 文件
-            }
-        }
+    // 开始扫描指定目录，或者展开宏之后的代码
+    let files = if settings.rustdoc_json.is_some() {
+        Vec::new()
+    } else if settings.expand {
+        expand::expand_roots(&settings)?
+    } else {
+        files::collect_files(&settings)?
+    };
+    if settings.dry_run {
+        print_file_list(&settings, &files);
+        return Ok(ExitCode::SUCCESS);
     }
 
-    Ok(())
-}
+    // 记录基线时保留全部结果，否则只保留基线中没有的新结果
+    let baseline = match &settings.baseline {
+        Some(path) if !settings.update_baseline => Some(Baseline::load(path)?),
+        _ => None,
+    };
+    let is_new = |finding: &Finding| baseline.as_ref().is_none_or(|baseline| !baseline.contains(finding));
 
-fn check_for_safe_versions(
-    unchecked_functions: Arc<Mutex<HashSet<(String, String)>>>,
-) -> Result<HashSet<(String, String, String)>> {
-    let mut results = HashSet::<(String, String, String)>::new();
-    let output = unchecked_functions.lock().unwrap();
-
-    for (file_path, func_name) in output.iter() {
-        // 生成安全版本的函数名
-        let safe_func_name = func_name.replace("_unchecked", "");
-
-        // 读取文件内容
-        let file_content = fs::read_to_string(file_path)?;
-        let parsed_file = parse_file(&file_content)?;
-
-        let mut found_safe_func = false;
-        let mut func_safety=true;
-
-        // 遍历文件中的所有项，查找具有相同名称的安全版本函数
-        for item in parsed_file.items {
-            match item {
-                syn::Item::Fn(item_fn) => {
-                    if item_fn.sig.ident.to_string() == safe_func_name {
-                        found_safe_func = true;
-                        if item_fn.sig.unsafety.is_some() {
-                            func_safety=false;
-                        }
-                        break;
-                    }
-                }
-                syn::Item::Impl(item_impl) => {
-                    // 遍历 impl 块中的所有方法
-                    for impl_item in item_impl.items {
-                        if let ImplItem::Fn(impl_fn) = impl_item {
-                            if impl_fn.sig.ident.to_string() == safe_func_name {
-                                found_safe_func = true;
-                                if impl_fn.sig.unsafety.is_some() {
-                                    func_safety=false;
-                                }
-                                break;
-                            }
-                        }
-                    }
-                }
-                _ => {}
+    let streaming = settings.format == OutputFormat::Ndjson;
+    let ScanResults { findings: mut safe_version_results, files: file_stats } = if streaming {
+        // 每处理完一个文件就输出其中的结果，不等整个扫描结束
+        let out = Mutex::new(open_output(&settings.output)?);
+        let on_finding = |finding: &Finding| {
+            if is_new(finding) {
+                report::ndjson::write_line(&mut *out.lock().unwrap(), finding)?;
             }
+            Ok(())
+        };
+        match &settings.rustdoc_json {
+            Some(path) => rustdoc::scan_each(&settings, path, on_finding)?,
+            None => scan::scan_each(&settings, &files, on_finding)?,
+        }
+    } else {
+        match &settings.rustdoc_json {
+            Some(path) => rustdoc::scan(&settings, path)?,
+            None => scan::scan(&settings, &files)?,
         }
+    };
 
-        // 根据查找结果更新结果集
-        if found_safe_func {
-            if func_safety{
-                results.insert((file_path.clone(), func_name.clone(), safe_func_name));
-            } else {
-                results.insert((file_path.clone(), func_name.clone(), format!("{safe_func_name}(unsafe)")));
-            }
-            
+    if let Some(path) = &settings.baseline {
+        if settings.update_baseline {
+            Baseline::write(path, &safe_version_results)?;
+            log::info!("Baseline with {} findings has been written to {}", safe_version_results.len(), path.display());
         } else {
-            results.insert((file_path.clone(), func_name.clone(), "None".to_string()));
+            let total = safe_version_results.len();
+            safe_version_results.retain(is_new);
+            log::info!("{} of {} findings are already in the baseline", total - safe_version_results.len(), total);
         }
     }
 
-    Ok(results)
-}
-
-
-fn main() -> Result<()> {
-    let crate_dir = r"library"; // 替换为你的 Rust 标准库路径
+    if streaming {
+        if let Output::File(path) = &settings.output {
+            log::info!("Safe version results have been written to {}", path.display());
+        }
+    } else if settings.format == OutputFormat::Dot {
+        let graph = callgraph::build(&settings, &files);
+        report::dot::write(&mut open_output(&settings.output)?, &graph, &safe_version_results)?;
+    } else {
+        let options = ReportOptions { files: Some(file_stats), ..settings.report_options.clone() };
+        write_output(&settings.output, settings.format, &safe_version_results, &options)?;
+    }
 
-    let unchecked_functions = Arc::new(Mutex::new(HashSet::<(String, String)>::new()));
+    if let Some(violation) = settings.fail_policy.check(&safe_version_results) {
+        log::error!("{}", violation);
+        return Ok(ExitCode::FAILURE);
+    }
 
-    process_directory(crate_dir, &unchecked_functions)?; // 开始扫描指定目录
+    Ok(ExitCode::SUCCESS)
+}
 
-    // 检查未检查函数是否对应有安全版本
-    let safe_version_results = check_for_safe_versions(unchecked_functions)?;
+// 下载 crates.io 上的 crate 后按 --workspace 扫描，以便报告中有包名和版本；
+// cargo metadata 不能读取的包直接扫描解压出的目录
+fn run_crate(args: &CrateArgs) -> Result<ExitCode> {
+    let (dir, _temp_dir) = download_dir(args.dir.as_deref())?;
+    let package_dir = registry::download(&args.spec, &dir)?;
+    let workspace = workspace::is_readable(&package_dir);
+    if !workspace {
+        log::warn!("cargo metadata failed in {}, scanning it without package information", package_dir.display());
+    }
+    run_scan(&ScanArgs { roots: vec![package_dir], workspace, ..args.scan.clone() })
+}
 
-    // 计算最大宽度
-    let max_file_path_len = safe_version_results.iter().map(|(path, _, _)| path.len()).max().unwrap_or(0);
-    let max_unchecked_func_len = safe_version_results.iter().map(|(_, func, _)| func.len()).max().unwrap_or(0);
-    let max_safe_func_len = safe_version_results.iter().map(|(_, _, safe_func)| safe_func.len()).max().unwrap_or(0);
-    
-    // 将检查结果输出到文件
-    let mut file = File::create("safe_version_results.txt")?;
-    writeln!(file, "| {:a$} | {:b$} | {:c$} |", "File Path", "Unchecked Function", "Safe Function", 
-             a=max_file_path_len+2, b=max_unchecked_func_len+2, c=max_safe_func_len+2)?;
-    writeln!(file, "|{:-<a$}|{:-<b$}|{:-<c$}|", "", "", "", 
-             a=max_file_path_len+2, b=max_unchecked_func_len+2, c=max_safe_func_len+2)?;
-             
-    for (file_path, unchec_func, safe_func) in safe_version_results {
-        writeln!(file, "| {:a$} | {:b$} | {:c$} |", file_path, unchec_func, safe_func, 
-                 a=max_file_path_len+2, b=max_unchecked_func_len+2, c=max_safe_func_len+2)?; // 写入结果
+// 克隆仓库后扫描 --path 指定的目录，没有时扫描整个仓库
+fn run_git(args: &GitArgs) -> Result<ExitCode> {
+    if args.path.as_ref().is_some_and(|path| path.is_absolute()) {
+        bail!("--path must be relative to the root of the repository");
+    }
+    let (dir, _temp_dir) = download_dir(args.dir.as_deref())?;
+    git::checkout(&args.url, args.branch.as_deref(), args.path.as_deref(), &dir)?;
+    let root = args.path.as_ref().map_or(dir.clone(), |path| dir.join(path));
+    if !root.is_dir() {
+        bail!("{} is not a directory of {}", root.display(), args.url);
     }
+    run_scan(&ScanArgs { roots: vec![root], ..args.scan.clone() })
+}
 
-    println!("Safe version results have been written to safe_version_results.txt");
+// --dir 指定的目录，没有时是一个临时目录，返回的 TempDir 被丢弃时删除
+fn download_dir(dir: Option<&Path>) -> Result<(PathBuf, Option<TempDir>)> {
+    match dir {
+        Some(dir) => {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+            Ok((dir.to_path_buf(), None))
+        }
+        None => {
+            let temp_dir = tempfile::tempdir()?;
+            Ok((temp_dir.path().to_path_buf(), Some(temp_dir)))
+        }
+    }
+}
 
-    Ok(())
+// 重新渲染之前保存的 json 报告，不需要再次扫描
+fn run_report(args: &ReportArgs) -> Result<ExitCode> {
+    let (results, files) = report::read_json_with_stats(&args.input)?;
+    let output = args.output.clone().map_or(Output::Stdout, Output::from_path);
+    let options = ReportOptions {
+        group: args.group,
+        sort: args.sort.unwrap_or_default(),
+        template: args.template.clone(),
+        snippet: args.snippet,
+        files,
+        ..ReportOptions::default()
+    };
+    write_output(&output, args.format.unwrap_or_default(), &results, &options)?;
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,199 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use regex::Regex;
+use serde::Deserialize;
+
+/// Built-in naming conventions for functions that skip a check, selected
+/// with `--convention` or by name in the config file's `conventions` list.
+#[derive(Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Builtin {
+    /// std's `get_unchecked` -> `get`, `unchecked_add` -> `add`
+    Unchecked,
+    /// `as_slice_raw` -> `as_slice`
+    Raw,
+    /// `assume_init`, `assume_init_read` and the rest of the family, which
+    /// have no safe counterpart
+    Assume,
+    /// `sort_unstable` -> `sort`
+    Unstable,
+    /// `get_unsynchronized` -> `get`
+    Unsynchronized,
+}
+
+impl Builtin {
+    fn spec(self) -> CustomConvention {
+        let (pattern, safe_name) = match self {
+            Builtin::Unchecked => ("unchecked", None),
+            Builtin::Raw => ("_raw$", None),
+            // 整个名字替换成空的，即没有安全版本
+            Builtin::Assume => ("^assume_init.*", Some("")),
+            Builtin::Unstable => ("_unstable$", None),
+            Builtin::Unsynchronized => ("unsynchronized", None),
+        };
+        CustomConvention { pattern: pattern.to_string(), safe_name: safe_name.map(str::to_string) }
+    }
+}
+
+/// A `conventions` entry in the config file: either the name of a built-in
+/// convention or a table with a pattern and an optional replacement.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum ConventionSpec {
+    Builtin(Builtin),
+    Custom(CustomConvention),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CustomConvention {
+    pub pattern: String,
+    // 安全版本名字的替换模板，可以引用捕获组，例如 `$1`；不设置时去掉匹配的部分
+    #[serde(default)]
+    pub safe_name: Option<String>,
+}
+
+/// A compiled naming convention: which names it matches and how the name of
+/// the safe counterpart is derived from them.
+#[derive(Debug, Clone)]
+pub struct Convention {
+    pattern: Regex,
+    safe_name: Option<String>,
+}
+
+impl Convention {
+    pub fn new(spec: &ConventionSpec) -> Result<Self> {
+        let spec = match spec {
+            ConventionSpec::Builtin(builtin) => builtin.spec(),
+            ConventionSpec::Custom(custom) => custom.clone(),
+        };
+        let pattern = Regex::new(&spec.pattern).with_context(|| format!("invalid pattern `{}`", spec.pattern))?;
+        Ok(Convention { pattern, safe_name: spec.safe_name })
+    }
+
+    /// A convention that matches `pattern` and strips the match.
+    pub fn stripping(pattern: &str) -> Result<Self> {
+        Convention::new(&ConventionSpec::Custom(CustomConvention { pattern: pattern.to_string(), safe_name: None }))
+    }
+
+    /// Name of the safe counterpart of `func_name`, or `None` if this
+    /// convention does not apply to it or derives no different name.
+    pub fn safe_name(&self, func_name: &str) -> Option<String> {
+        if !self.pattern.is_match(func_name) {
+            return None;
+        }
+        let safe_name = match &self.safe_name {
+            Some(replacement) => self.pattern.replace_all(func_name, replacement.as_str()).into_owned(),
+            None => strip(&self.pattern, func_name),
+        };
+        if safe_name.is_empty() || safe_name == func_name {
+            None
+        } else {
+            Some(safe_name)
+        }
+    }
+}
+
+/// One pattern matching every name any of the conventions matches.
+pub fn combined(conventions: &[Convention]) -> Regex {
+    let alternatives: Vec<String> = conventions.iter().map(|convention| format!("(?:{})", convention.pattern)).collect();
+    // 每个模式都已经单独编译过，合在一起不会出错
+    Regex::new(&alternatives.join("|")).unwrap()
+}
+
+/// The safe counterpart's name according to the first convention that
+/// matches `func_name`.
+pub fn derive_safe_name(conventions: &[Convention], func_name: &str) -> Option<String> {
+    conventions
+        .iter()
+        .find(|convention| convention.pattern.is_match(func_name))
+        .and_then(|convention| convention.safe_name(func_name))
+}
+
+/// Names the safe counterpart of `func_name` may have, most likely first:
+/// the derived name, then its `checked_`, `try_` and `_checked` forms, so
+/// that `unchecked_add` also finds `checked_add`.
+pub fn safe_name_candidates(conventions: &[Convention], func_name: &str) -> Vec<String> {
+    let Some(safe_name) = derive_safe_name(conventions, func_name) else {
+        return Vec::new();
+    };
+    let alternatives = [format!("checked_{safe_name}"), format!("try_{safe_name}"), format!("{safe_name}_checked")];
+    std::iter::once(safe_name)
+        .chain(alternatives)
+        .filter(|candidate| candidate != func_name)
+        .collect()
+}
+
+// 去掉名字中匹配 pattern 的部分（连同相邻的一个下划线），
+// 例如 get_unchecked_mut -> get_mut, unchecked_add -> add
+fn strip(pattern: &Regex, func_name: &str) -> String {
+    let mut safe_name = String::new();
+    let mut last = 0;
+    for m in pattern.find_iter(func_name) {
+        let mut start = m.start();
+        let mut end = m.end();
+        if start > last && func_name[..start].ends_with('_') {
+            start -= 1;
+        } else if func_name[end..].starts_with('_') {
+            end += 1;
+        }
+        safe_name.push_str(&func_name[last..start]);
+        last = end;
+    }
+    safe_name.push_str(&func_name[last..]);
+    safe_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unchecked() -> Vec<Convention> {
+        vec![Convention::stripping("unchecked").unwrap()]
+    }
+
+    #[test]
+    fn strip_removes_one_adjacent_underscore() {
+        let pattern = Regex::new("unchecked").unwrap();
+        assert_eq!(strip(&pattern, "get_unchecked_mut"), "get_mut");
+        assert_eq!(strip(&pattern, "unchecked_add"), "add");
+        assert_eq!(strip(&pattern, "from_utf8_unchecked"), "from_utf8");
+        assert_eq!(strip(&pattern, "uncheckedfoo"), "foo");
+    }
+
+    #[test]
+    fn safe_name_candidates_include_checked_and_try_forms() {
+        assert_eq!(
+            safe_name_candidates(&unchecked(), "unchecked_add"),
+            ["add", "checked_add", "try_add", "add_checked"]
+        );
+        assert!(safe_name_candidates(&unchecked(), "get").is_empty());
+        assert!(safe_name_candidates(&unchecked(), "unchecked").is_empty());
+    }
+
+    #[test]
+    fn first_matching_convention_derives_the_name() {
+        let conventions = vec![
+            Convention::new(&ConventionSpec::Builtin(Builtin::Raw)).unwrap(),
+            Convention::new(&ConventionSpec::Builtin(Builtin::Unchecked)).unwrap(),
+        ];
+        assert_eq!(derive_safe_name(&conventions, "as_slice_raw").as_deref(), Some("as_slice"));
+        assert_eq!(derive_safe_name(&conventions, "get_unchecked").as_deref(), Some("get"));
+    }
+
+    #[test]
+    fn replacement_template_can_use_capture_groups() {
+        let custom = CustomConvention { pattern: "^(\\w+)_nocheck$".to_string(), safe_name: Some("checked_$1".to_string()) };
+        let convention = Convention::new(&ConventionSpec::Custom(custom)).unwrap();
+        assert_eq!(convention.safe_name("add_nocheck").as_deref(), Some("checked_add"));
+    }
+
+    #[test]
+    fn assume_init_family_has_no_safe_counterpart() {
+        let assume = Convention::new(&ConventionSpec::Builtin(Builtin::Assume)).unwrap();
+        assert!(combined(std::slice::from_ref(&assume)).is_match("assume_init_read"));
+        assert_eq!(assume.safe_name("assume_init_read"), None);
+        assert_eq!(assume.safe_name("assume_init"), None);
+        assert!(!combined(&[assume]).is_match("assume_aligned"));
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use crate::finding::{Finding, SafeCounterpart};
+
+/// Conditions under which a scan should end with a nonzero exit status.
+#[derive(Debug, Default)]
+pub struct FailPolicy {
+    // 允许的最多“没有安全版本”的函数数量，None 表示不限制
+    pub max_findings: Option<usize>,
+    // 只要有函数完全找不到安全版本就失败，安全版本本身是 unsafe 的不算
+    pub fail_on_missing_safe: bool,
+}
+
+impl FailPolicy {
+    /// Returns a description of the violated threshold, if any. Only unchecked
+    /// function definitions count towards a threshold, and deprecated ones
+    /// never do.
+    pub fn check(&self, findings: &HashSet<Finding>) -> Option<String> {
+        if self.fail_on_missing_safe {
+            let missing = findings
+                .iter()
+                .filter(|finding| counts(finding) && finding.safe_counterpart == SafeCounterpart::Missing)
+                .count();
+            if missing > 0 {
+                return Some(format!("{missing} unchecked functions have no safe version at all"));
+            }
+        }
+
+        let max_findings = self.max_findings?;
+        let count = findings.iter().filter(|finding| counts(finding) && !finding.safe_counterpart.is_safe()).count();
+        if count > max_findings {
+            Some(format!(
+                "{count} unchecked functions have no safe counterpart (maximum allowed: {max_findings})"
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+// unsafe 块、static mut 等其他种类的结果没有安全版本可言
+fn counts(finding: &Finding) -> bool {
+    finding.is_unchecked_function() && !finding.deprecated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finding::FindingKind;
+
+    fn findings() -> HashSet<Finding> {
+        let deprecated = Finding { deprecated: true, ..Finding::for_test("old_unchecked", FindingKind::Function, SafeCounterpart::Missing) };
+        HashSet::from([
+            Finding::for_test("get_unchecked", FindingKind::Method, SafeCounterpart::Safe("get".to_string())),
+            Finding::for_test("add_unchecked", FindingKind::Function, SafeCounterpart::Unsafe("add".to_string())),
+            Finding::for_test("COUNTER", FindingKind::StaticMut, SafeCounterpart::Missing),
+            Finding::for_test("get", FindingKind::UnsafeBlocks, SafeCounterpart::Missing),
+            Finding::for_test("get", FindingKind::SafeWrapper, SafeCounterpart::Missing),
+            deprecated,
+        ])
+    }
+
+    #[test]
+    fn other_kinds_never_count_as_missing() {
+        let policy = FailPolicy { fail_on_missing_safe: true, ..FailPolicy::default() };
+        assert_eq!(policy.check(&findings()), None);
+
+        let mut findings = findings();
+        findings.insert(Finding::for_test("read_unchecked", FindingKind::Ffi, SafeCounterpart::Missing));
+        assert_eq!(policy.check(&findings).as_deref(), Some("1 unchecked functions have no safe version at all"));
+    }
+
+    #[test]
+    fn max_findings_counts_unsafe_counterparts() {
+        let policy = FailPolicy { max_findings: Some(1), ..FailPolicy::default() };
+        assert_eq!(policy.check(&findings()), None);
+        let policy = FailPolicy { max_findings: Some(0), ..FailPolicy::default() };
+        assert!(policy.check(&findings()).is_some_and(|message| message.starts_with("1 unchecked functions")));
+    }
+
+    #[test]
+    fn unsafe_fn_not_matching_the_pattern_does_not_count() {
+        let unsafe_fn = Finding { name_matches: false, ..Finding::for_test("read", FindingKind::Function, SafeCounterpart::Missing) };
+        let policy = FailPolicy { max_findings: Some(0), fail_on_missing_safe: true };
+        assert_eq!(policy.check(&HashSet::from([unsafe_fn])), None);
+    }
+}
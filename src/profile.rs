@@ -0,0 +1,45 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::report::OutputFormat;
+
+/// A named bundle of settings selected with `--profile` or the config file's
+/// `profile` key. The config file and CLI flags are applied on top of it.
+#[derive(Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    /// Audit the rust-lang `library/` tree: skip tests, benches and Miri
+    /// shims, and write a Markdown report
+    StdAudit,
+    /// Gate an application crate in CI: fail on any unchecked function
+    /// without a safe counterpart
+    Strict,
+    /// Scan an application crate's own sources without failing the run
+    AppDefault,
+}
+
+impl Profile {
+    pub fn config(self) -> Config {
+        match self {
+            Profile::StdAudit => Config {
+                exclude: strings(&["tests", "tests.rs", "benches", "*.miri.rs"]),
+                format: Some(OutputFormat::Markdown),
+                ..Config::default()
+            },
+            Profile::Strict => Config {
+                exclude: strings(&["target"]),
+                fail_on_findings: Some(true),
+                ..Config::default()
+            },
+            Profile::AppDefault => Config {
+                exclude: strings(&["target", "tests", "benches", "examples"]),
+                ..Config::default()
+            },
+        }
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
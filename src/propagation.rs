@@ -0,0 +1,110 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// A function definition in the call graph.
+pub struct CallNode<'a> {
+    pub crate_name: &'a str,
+    pub owner: Option<&'a str>,
+    pub name: &'a str,
+    pub calls: &'a HashSet<Callee>,
+}
+
+/// A called function as far as it is known without type checking. A call
+/// links to the functions of that name and owner in the same crate, or to
+/// every function of that name when the owner is unknown, as for method
+/// calls on anything but `self`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Callee {
+    pub name: String,
+    pub owner: Option<String>,
+    // owner 是否是确定的：`Vec::new()` 中写明了类型，`f()` 和 `ptr::read()` 只能是自由函数。
+    // 通过 self 或 Self 的调用可能是 trait 中的方法，找不到同类型的函数时匹配所有同名函数
+    pub explicit: bool,
+}
+
+impl Callee {
+    pub fn named(name: String) -> Self {
+        Callee { name, owner: None, explicit: false }
+    }
+}
+
+/// How a function reaches an unchecked function: the functions it goes
+/// through, as indices into the nodes, and the unchecked function called at
+/// the end. `via` is empty when the function calls it directly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chain {
+    pub via: Vec<usize>,
+    pub callee: String,
+}
+
+// 到达 unchecked 函数的下一步
+#[derive(Clone)]
+enum Step {
+    Direct(String),
+    Via(usize),
+}
+
+/// The shortest chain from each of `nodes` to a call of a function for which
+/// `is_unchecked` holds, or `None` if no chain within the crate leads to one.
+/// Of chains of the same length, the one through earlier nodes wins.
+pub fn shortest_chains(nodes: &[CallNode], is_unchecked: impl Fn(&str) -> bool) -> Vec<Option<Chain>> {
+    let mut by_name: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+    for (index, node) in nodes.iter().enumerate() {
+        by_name.entry((node.crate_name, node.name)).or_default().push(index);
+    }
+    let targets = |node: &CallNode, callee: &Callee| -> Vec<usize> {
+        let candidates = by_name.get(&(node.crate_name, callee.name.as_str())).map(Vec::as_slice).unwrap_or_default();
+        let same_owner: Vec<usize> =
+            candidates.iter().copied().filter(|&index| nodes[index].owner == callee.owner.as_deref()).collect();
+        if callee.explicit || (callee.owner.is_some() && !same_owner.is_empty()) {
+            same_owner
+        } else {
+            candidates.to_vec()
+        }
+    };
+
+    // 反向的边：被调用的函数 -> 调用它的函数
+    let mut callers: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut next: Vec<Option<Step>> = vec![None; nodes.len()];
+    let mut queue = VecDeque::new();
+    for (index, node) in nodes.iter().enumerate() {
+        let mut calls: Vec<&Callee> = node.calls.iter().collect();
+        calls.sort();
+        if let Some(callee) = calls.iter().find(|callee| is_unchecked(&callee.name)) {
+            next[index] = Some(Step::Direct(callee.name.clone()));
+            queue.push_back(index);
+        }
+        for callee in calls {
+            for target in targets(node, callee) {
+                if target != index {
+                    callers[target].push(index);
+                }
+            }
+        }
+    }
+
+    while let Some(target) = queue.pop_front() {
+        for &caller in &callers[target] {
+            if next[caller].is_none() {
+                next[caller] = Some(Step::Via(target));
+                queue.push_back(caller);
+            }
+        }
+    }
+
+    (0..nodes.len())
+        .map(|index| {
+            let mut via = Vec::new();
+            let mut step = next[index].as_ref()?;
+            loop {
+                match step {
+                    Step::Direct(callee) => return Some(Chain { via, callee: callee.clone() }),
+                    Step::Via(target) => {
+                        via.push(*target);
+                        // 广度优先得到的 next 不会成环
+                        step = next[*target].as_ref()?;
+                    }
+                }
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+
+// crates.io 要求请求带有能识别出工具的 User-Agent
+const USER_AGENT: &str = concat!("scan_unchecked/", env!("CARGO_PKG_VERSION"));
+const API_URL: &str = "https://crates.io/api/v1/crates";
+const DOWNLOAD_URL: &str = "https://static.crates.io/crates";
+
+/// A crate on crates.io as given to the `crate` subcommand, `NAME` or
+/// `NAME@VERSION`, where VERSION may leave out its minor or patch number.
+#[derive(Debug, Clone)]
+pub struct CrateSpec {
+    pub name: String,
+    // 没有指定时使用最新的稳定版本，`1.2` 表示最新的 1.2.x
+    pub version: Option<String>,
+}
+
+impl FromStr for CrateSpec {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let (name, version) = match spec.split_once('@') {
+            Some((name, version)) => (name, Some(version.to_string())),
+            None => (spec, None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("invalid crate name `{name}`"));
+        }
+        if version.as_ref().is_some_and(|version| version.is_empty() || version.contains('/')) {
+            return Err(format!("invalid version in `{spec}`"));
+        }
+        Ok(CrateSpec { name: name.to_string(), version })
+    }
+}
+
+impl fmt::Display for CrateSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{}@{}", self.name, version),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+// `GET /api/v1/crates/名字` 的响应中用到的部分
+#[derive(Deserialize)]
+struct CrateResponse {
+    #[serde(rename = "crate")]
+    krate: CrateInfo,
+    versions: Vec<VersionInfo>,
+}
+
+#[derive(Deserialize)]
+struct CrateInfo {
+    max_stable_version: Option<String>,
+    max_version: String,
+}
+
+#[derive(Deserialize)]
+struct VersionInfo {
+    num: String,
+    yanked: bool,
+}
+
+/// `spec` with the exact version to download: the latest stable version
+/// when it has none, or the latest version matching a partial one like
+/// `1.2`. Only these cases ask crates.io.
+pub fn resolve(spec: &CrateSpec) -> Result<CrateSpec> {
+    let version = match &spec.version {
+        Some(version) if semver::Version::parse(version).is_ok() => version.clone(),
+        Some(version) => matching_version(&spec.name, version)?,
+        None => latest_version(&spec.name)?,
+    };
+    Ok(CrateSpec { name: spec.name.clone(), version: Some(version) })
+}
+
+/// Downloads the `.crate` archive of `spec` from crates.io and unpacks it
+/// into `dir`. Returns the directory of the unpacked package,
+/// `dir/NAME-VERSION`.
+pub fn download(spec: &CrateSpec, dir: &Path) -> Result<PathBuf> {
+    let spec = resolve(spec)?;
+    let version = spec.version.unwrap_or_default();
+    log::info!("Downloading {}@{} from crates.io", spec.name, version);
+    let url = format!("{DOWNLOAD_URL}/{name}/{name}-{version}.crate", name = spec.name);
+    let response = match ureq::get(&url).set("User-Agent", USER_AGENT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404 | 403, _)) => bail!("{}@{} is not published on crates.io", spec.name, version),
+        Err(error) => return Err(error).with_context(|| format!("failed to download {url}")),
+    };
+
+    // tar 会拒绝解压到目标目录之外的路径
+    tar::Archive::new(GzDecoder::new(response.into_reader()))
+        .unpack(dir)
+        .with_context(|| format!("failed to unpack {url} into {}", dir.display()))?;
+    let package_dir = dir.join(format!("{}-{}", spec.name, version));
+    if !package_dir.join("Cargo.toml").is_file() {
+        bail!("{url} does not contain {}-{}/Cargo.toml", spec.name, version);
+    }
+    Ok(package_dir)
+}
+
+fn latest_version(name: &str) -> Result<String> {
+    let response = lookup(name)?;
+    // 只有预发布版本的 crate 没有稳定版本
+    Ok(response.krate.max_stable_version.unwrap_or(response.krate.max_version))
+}
+
+// 与 `=1.2` 一样匹配 1.2.x，跳过被撤回的版本
+fn matching_version(name: &str, version: &str) -> Result<String> {
+    let requirement = semver::VersionReq::parse(&format!("={version}"))
+        .ok()
+        .with_context(|| format!("invalid version `{version}` of {name}"))?;
+    let response = lookup(name)?;
+    let latest = response
+        .versions
+        .iter()
+        .filter(|info| !info.yanked)
+        .filter_map(|info| semver::Version::parse(&info.num).ok())
+        .filter(|candidate| requirement.matches(candidate))
+        .max();
+    match latest {
+        Some(latest) => Ok(latest.to_string()),
+        None => bail!("no version of {name} on crates.io matches {version}"),
+    }
+}
+
+fn lookup(name: &str) -> Result<CrateResponse> {
+    let url = format!("{API_URL}/{name}");
+    let response = match ureq::get(&url).set("User-Agent", USER_AGENT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => bail!("no crate named `{name}` on crates.io"),
+        Err(error) => return Err(error).with_context(|| format!("failed to look up {name} on crates.io")),
+    };
+    let body = response.into_string().with_context(|| format!("failed to read {url}"))?;
+    serde_json::from_str(&body).with_context(|| format!("invalid response from {url}"))
+}
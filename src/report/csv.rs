@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::checks::Check;
+use crate::finding::Finding;
+
+use super::ReportOptions;
+
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let mut writer = ::csv::Writer::from_writer(out);
+    writer.write_record([
+        "root",
+        "package",
+        "package_version",
+        "file",
+        "line",
+        "column",
+        "module_path",
+        "function",
+        "name_matches",
+        "owner",
+        "trait",
+        "signature",
+        "cfg",
+        "features",
+        "deprecated",
+        "const",
+        "async",
+        "stability",
+        "stability_feature",
+        "safety_doc",
+        "safety_section",
+        "debug_checked",
+        "caller",
+        "kind",
+        "context",
+        "category",
+        "count",
+        "call_chain",
+        "example_calls",
+        "callers",
+        "reexports",
+        "safe_counterpart",
+        "status",
+        "counterpart_signature",
+        "counterpart_relation",
+        "added_checks",
+    ])?;
+    for finding in super::sorted(results, options.sort) {
+        let safe_counterpart = finding.safe_counterpart.name().unwrap_or("");
+        writer.write_record([
+            finding.root.as_str(),
+            finding.package.as_ref().map_or("", |package| package.name.as_str()),
+            finding.package.as_ref().map_or("", |package| package.version.as_str()),
+            finding.file.as_str(),
+            &finding.span.line.to_string(),
+            &finding.span.column.to_string(),
+            finding.module_path.as_str(),
+            finding.function.as_str(),
+            if finding.name_matches { "true" } else { "false" },
+            finding.owner.as_deref().unwrap_or(""),
+            finding.trait_name.as_deref().unwrap_or(""),
+            finding.signature.as_deref().unwrap_or(""),
+            &finding.cfg.join("; "),
+            &finding.features.join(";"),
+            if finding.deprecated { "true" } else { "false" },
+            if finding.const_fn { "true" } else { "false" },
+            if finding.async_fn { "true" } else { "false" },
+            finding.stability.as_ref().map_or("", |stability| stability.level.name()),
+            finding.stability.as_ref().map_or("", |stability| stability.feature.as_str()),
+            optional_bool(finding.safety_doc),
+            finding.safety_section.as_deref().unwrap_or(""),
+            optional_bool(finding.debug_checked),
+            finding.caller.as_deref().unwrap_or(""),
+            finding.kind.name(),
+            finding.context.name(),
+            finding.category.name(),
+            &finding.count.map(|count| count.to_string()).unwrap_or_default(),
+            &finding.call_chain.join(" -> "),
+            &finding.example_calls.join(";"),
+            &callers(finding),
+            &finding.reexports.join("; "),
+            safe_counterpart,
+            finding.safe_counterpart.status(),
+            finding.counterpart_signature.map_or("", |comparison| comparison.name()),
+            finding.counterpart_relation.map_or("", |relation| relation.name()),
+            &added_checks(finding),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// 例如 `core::slice::Iter::next (src/slice/iter.rs:120)`，多个之间用分号分隔
+fn callers(finding: &Finding) -> String {
+    finding
+        .callers
+        .iter()
+        .map(|caller| format!("{} ({}:{})", caller.function, caller.file, caller.line))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// 例如 `bounds: index < self.len(); failure: None`
+fn added_checks(finding: &Finding) -> String {
+    finding.added_checks.iter().map(Check::describe).collect::<Vec<_>>().join("; ")
+}
+
+fn optional_bool(value: Option<bool>) -> &'static str {
+    match value {
+        Some(true) => "true",
+        Some(false) => "false",
+        None => "",
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::Result;
+use syn::{ReturnType, Signature, Type};
+
+use crate::config::DEFAULT_PATTERN;
+use crate::finding::{Finding, FindingKind, SafeCounterpart};
+use crate::naming::{self, Convention};
+use crate::scan;
+
+use super::snippet::Snippets;
+use super::table::paint;
+use super::ReportOptions;
+
+// 与 rustc 相同的配色
+const WARNING: &str = "\x1b[1;33m";
+const NOTE: &str = "\x1b[1;32m";
+const HELP: &str = "\x1b[1;36m";
+const GUTTER: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+
+/// Writes a rustc-style diagnostic for each unchecked function without a
+/// usable safe counterpart: a warning when there is none, a note when it is
+/// unsafe too or appears to validate nothing. Safe functions with an
+/// unchecked name get a warning as well. Each shows the source line of the
+/// finding and, for function definitions, the signature of a safe
+/// counterpart to add.
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let conventions = if options.conventions.is_empty() {
+        vec![Convention::stripping(DEFAULT_PATTERN)?]
+    } else {
+        options.conventions.clone()
+    };
+    let mut snippets = Snippets::new(0);
+    let mut warnings = 0;
+    for finding in super::sorted(results, options.sort) {
+        let (level, style, message) = match &finding.safe_counterpart {
+            _ if finding.kind == FindingKind::SafeUnchecked => {
+                ("warning", WARNING, "function named like an unchecked function is not `unsafe`".to_string())
+            }
+            // 其他种类的结果没有安全版本可找
+            _ if !finding.is_unchecked_function() => continue,
+            SafeCounterpart::Safe(_) => continue,
+            SafeCounterpart::Missing => ("warning", WARNING, "unchecked function without safe counterpart".to_string()),
+            SafeCounterpart::Unsafe(name) => ("note", NOTE, format!("unchecked function whose counterpart `{name}` is unsafe too")),
+            SafeCounterpart::Unvalidated(name) => {
+                ("note", NOTE, format!("safe counterpart `{name}` appears to perform no validation"))
+            }
+        };
+        warnings += usize::from(level == "warning");
+        let source = snippets.get(finding);
+        let gutter = " ".repeat(finding.span.line.to_string().len());
+        let bar = paint(&format!("{gutter} |"), GUTTER, options.color);
+
+        writeln!(out, "{}{}", paint(level, style, options.color), paint(&format!(": {message}"), BOLD, options.color))?;
+        writeln!(out, "{}{}", paint(&format!("{gutter}--> "), GUTTER, options.color), finding.location())?;
+        if let Some(source) = &source {
+            // 列号从 1 开始，标出函数名；找不到函数名时只标一个字符
+            let start = finding.span.column.saturating_sub(1);
+            let len = if source.chars().skip(start).collect::<String>().starts_with(&finding.function) {
+                finding.function.chars().count()
+            } else {
+                1
+            };
+            let label = match &finding.safe_counterpart {
+                _ if finding.kind == FindingKind::SafeUnchecked => "callers may expect this to skip checks".to_string(),
+                SafeCounterpart::Unsafe(name) => format!("`{name}` needs `unsafe` as well"),
+                SafeCounterpart::Unvalidated(name) => format!("`{name}` does not check what this function assumes"),
+                _ => format!("`{}` has no safe counterpart", finding.name()),
+            };
+            writeln!(out, "{bar}")?;
+            writeln!(out, "{} {}", paint(&format!("{} |", finding.span.line), GUTTER, options.color), source)?;
+            let marker = paint(&format!("{}{}", " ".repeat(start), "^".repeat(len)), style, options.color);
+            writeln!(out, "{bar} {marker} {}", paint(&label, style, options.color))?;
+        }
+        if let Some(section) = &finding.safety_section {
+            writeln!(out, "{bar}")?;
+            writeln!(out, "{} safety: {}", paint(&format!("{gutter} = note:"), BOLD, options.color), section.replace("\n\n", " "))?;
+        }
+        if let Some((help, suggestion)) = suggestion(finding, &conventions, source.as_deref()) {
+            writeln!(out, "{}{}", paint("help", HELP, options.color), paint(&format!(": {help}"), BOLD, options.color))?;
+            writeln!(out, "{bar}")?;
+            let added = paint(&format!("{} +", finding.span.line), GUTTER, options.color);
+            writeln!(out, "{added} {}", paint(&suggestion, HELP, options.color))?;
+            writeln!(out, "{bar}")?;
+        }
+        writeln!(out)?;
+    }
+    if warnings > 0 {
+        let plural = if warnings == 1 { "" } else { "s" };
+        writeln!(out, "{}{}", paint("warning", WARNING, options.color), paint(&format!(": {warnings} warning{plural} emitted"), BOLD, options.color))?;
+    }
+    Ok(())
+}
+
+// 建议的安全版本：去掉 unsafe，改用安全版本的名字，返回值包在 Option 中，
+// 已经返回 Option 或 Result、或者没有返回值（应当 panic）的不变。
+// 没有签名的结果（例如调用）和看不出安全版本名字的没有建议
+fn suggestion(finding: &Finding, conventions: &[Convention], source: Option<&str>) -> Option<(String, String)> {
+    if finding.kind == FindingKind::SafeUnchecked {
+        return None;
+    }
+    let (help, safe_name) = match &finding.safe_counterpart {
+        SafeCounterpart::Missing => (
+            "add a safe counterpart that checks the precondition".to_string(),
+            naming::derive_safe_name(conventions, &finding.function)?,
+        ),
+        SafeCounterpart::Unsafe(name) => (format!("make `{name}` safe by checking the precondition"), name.clone()),
+        SafeCounterpart::Safe(_) | SafeCounterpart::Unvalidated(_) => return None,
+    };
+    let signature = finding.signature.as_deref()?;
+    let mut sig: Signature = syn::parse_str(signature).ok()?;
+    sig.unsafety = None;
+    sig.ident = syn::parse_str(&safe_name).ok()?;
+    if let ReturnType::Type(_, ty) = &sig.output {
+        if !is_fallible(ty) {
+            sig.output = syn::parse_quote!(-> Option<#ty>);
+        }
+    }
+
+    // 保留源码中签名前面的缩进和可见性
+    let first_word = signature.split_whitespace().next().unwrap_or_default();
+    let prefix = source
+        .and_then(|line| line.find(&format!("{first_word} ")).map(|index| &line[..index]))
+        .unwrap_or_default();
+    Some((help, format!("{prefix}{}", scan::render_signature(&sig))))
+}
+
+fn is_fallible(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path.path.segments.last().is_some_and(|segment| segment.ident == "Option" || segment.ident == "Result"),
+        _ => false,
+    }
+}
@@ -0,0 +1,49 @@
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::callgraph::CallGraph;
+use crate::finding::{Finding, SafeCounterpart};
+
+/// Writes the call graph in Graphviz DOT format. Unchecked functions found in
+/// the scan are colored by the status of their safe counterpart; callees that
+/// are not defined in the scanned files are drawn dashed.
+pub fn write(out: &mut dyn Write, graph: &CallGraph, results: &HashSet<Finding>) -> Result<()> {
+    // 同名函数有多个定义时取最差的状态
+    let mut colors: HashMap<&str, (u8, &str)> = HashMap::new();
+    for finding in results {
+        let color = match finding.safe_counterpart {
+            SafeCounterpart::Missing => (3, "red"),
+            SafeCounterpart::Unsafe(_) => (2, "orange"),
+            SafeCounterpart::Unvalidated(_) => (1, "goldenrod"),
+            SafeCounterpart::Safe(_) => (0, "darkgreen"),
+        };
+        let entry = colors.entry(&finding.function).or_insert(color);
+        *entry = (*entry).max(color);
+    }
+
+    writeln!(out, "digraph unchecked_calls {{")?;
+    writeln!(out, "    rankdir=LR;")?;
+    writeln!(out, "    node [shape=box, fontname=\"monospace\"];")?;
+
+    let mut callees: Vec<&str> = graph.edges.iter().map(|(_, callee)| callee.as_str()).collect();
+    callees.sort();
+    callees.dedup();
+    for callee in callees {
+        match colors.get(callee) {
+            Some((_, color)) => writeln!(out, "    {} [color={color}];", quote(callee))?,
+            None => writeln!(out, "    {} [style=dashed];", quote(callee))?,
+        }
+    }
+    for (caller, callee) in &graph.edges {
+        writeln!(out, "    {} -> {};", quote(caller), quote(callee))?;
+    }
+
+    writeln!(out, "}}")?;
+    Ok(())
+}
+
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::finding::Finding;
+
+use super::snippet::Snippets;
+use super::ReportOptions;
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.4em; }
+.controls { display: flex; gap: 1em; margin-bottom: 1em; flex-wrap: wrap; }
+.controls input { width: 20em; }
+details { margin-bottom: .5em; border: 1px solid #ddd; border-radius: 4px; }
+summary { padding: .4em .6em; background: #f6f8fa; cursor: pointer; font-family: monospace; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: .3em .6em; border-top: 1px solid #eee; font-family: monospace; }
+th { font-family: inherit; }
+pre { margin: 0; }
+.safe { color: #1a7f37; }
+.unsafe { color: #9a6700; }
+.unvalidated { color: #9a6700; }
+.missing { color: #cf222e; }
+.safety { font-family: inherit; white-space: pre-wrap; max-width: 40em; }
+.hidden { display: none; }
+"#;
+
+const SCRIPT: &str = r#"
+const search = document.getElementById('search');
+const crate = document.getElementById('crate');
+const status = document.getElementById('status');
+const sort = document.getElementById('sort');
+const list = document.getElementById('files');
+
+function update() {
+  const text = search.value.toLowerCase();
+  let shown = 0;
+  for (const file of list.children) {
+    let visible = 0;
+    for (const row of file.querySelectorAll('tbody tr')) {
+      const match = (!text || row.dataset.search.includes(text))
+        && (!crate.value || file.dataset.crate === crate.value)
+        && (!status.value || row.dataset.status === status.value);
+      row.classList.toggle('hidden', !match);
+      if (match) visible++;
+    }
+    file.classList.toggle('hidden', visible === 0);
+    file.querySelector('.count').textContent = visible;
+    shown += visible;
+  }
+  document.getElementById('shown').textContent = shown;
+}
+
+function reorder() {
+  const key = sort.value;
+  const files = [...list.children];
+  for (const file of files) {
+    const body = file.querySelector('tbody');
+    const rows = [...body.children];
+    rows.sort((a, b) => key === 'line'
+      ? a.dataset.line - b.dataset.line
+      : a.dataset[key].localeCompare(b.dataset[key]) || a.dataset.line - b.dataset.line);
+    body.append(...rows);
+  }
+  files.sort((a, b) => key === 'status'
+    ? a.dataset.missing - b.dataset.missing || a.dataset.file.localeCompare(b.dataset.file)
+    : a.dataset.file.localeCompare(b.dataset.file));
+  if (key === 'status') files.reverse();
+  list.append(...files);
+}
+
+for (const input of [search, crate, status]) input.addEventListener('input', update);
+sort.addEventListener('input', reorder);
+document.getElementById('expand').addEventListener('click', () => list.querySelectorAll('details').forEach(d => d.open = true));
+document.getElementById('collapse').addEventListener('click', () => list.querySelectorAll('details').forEach(d => d.open = false));
+"#;
+
+/// Writes a single HTML page with one collapsible section per file. Searching,
+/// filtering and sorting run in the browser, so the file can be shared as is.
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let mut by_file: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+    for finding in super::sorted(results, options.sort) {
+        by_file.entry(finding.file.as_str()).or_default().push(finding);
+    }
+    let crates: BTreeSet<&str> = results.iter().map(Finding::crate_name).collect();
+    let summary = super::Summary::new(results);
+    let mut snippets = options.snippet.map(Snippets::new);
+    // 有 `# Safety` 一节时多一列，报告同时是安全前提的清单
+    let safety_column = results.iter().any(|finding| finding.safety_section.is_some());
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\">")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Unchecked functions</title>")?;
+    writeln!(out, "<style>{STYLE}</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>Unchecked functions</h1>")?;
+    writeln!(
+        out,
+        "<p><span id=\"shown\">{}</span> of {} shown &middot; {} unchecked functions, {} with a safe version ({:.1}%), {} with an unsafe version, {} without</p>",
+        results.len(),
+        results.len(),
+        summary.total,
+        summary.safe,
+        summary.coverage(),
+        summary.unsafe_,
+        summary.missing,
+    )?;
+    if let Some(files) = options.files {
+        writeln!(out, "<p>{} files scanned, {} skipped (unreadable or unparseable)</p>", files.scanned, files.skipped)?;
+    }
+    if !summary.categories.is_empty() {
+        writeln!(out, "<ul class=\"categories\">")?;
+        for (category, count) in &summary.categories {
+            writeln!(
+                out,
+                "<li><b>{}</b> ({}): {}</li>",
+                category.name(),
+                count,
+                super::escape_xml(category.invariant()),
+            )?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    writeln!(out, "<div class=\"controls\">")?;
+    writeln!(out, "<input id=\"search\" type=\"search\" placeholder=\"Search functions, modules and files\">")?;
+    writeln!(out, "<select id=\"crate\"><option value=\"\">All crates</option>")?;
+    for name in &crates {
+        writeln!(out, "<option>{}</option>", super::escape_xml(name))?;
+    }
+    writeln!(out, "</select>")?;
+    writeln!(out, "<select id=\"status\"><option value=\"\">Any status</option><option value=\"safe\">Safe version</option><option value=\"unvalidated\">Safe version without validation</option><option value=\"unsafe\">Unsafe version</option><option value=\"missing\">No safe version</option></select>")?;
+    writeln!(out, "<select id=\"sort\"><option value=\"line\">Sort by line</option><option value=\"name\">Sort by name</option><option value=\"status\">Sort by status</option></select>")?;
+    writeln!(out, "<button id=\"expand\">Expand all</button><button id=\"collapse\">Collapse all</button>")?;
+    writeln!(out, "</div>")?;
+
+    writeln!(out, "<div id=\"files\">")?;
+    for (file, findings) in &by_file {
+        let missing = findings.iter().filter(|finding| !finding.safe_counterpart.is_safe()).count();
+        writeln!(
+            out,
+            "<details open data-file=\"{}\" data-crate=\"{}\" data-missing=\"{}\">",
+            super::escape_xml(file),
+            super::escape_xml(findings[0].crate_name()),
+            missing,
+        )?;
+        writeln!(
+            out,
+            "<summary>{} (<span class=\"count\">{}</span>) &middot; {}</summary>",
+            super::escape_xml(file),
+            findings.len(),
+            super::escape_xml(&findings[0].module_path),
+        )?;
+        let safety_header = if safety_column { "<th>Safety</th>" } else { "" };
+        let source_header = if snippets.is_some() { "<th>Source</th>" } else { "" };
+        writeln!(out, "<table><thead><tr><th>Position</th><th>Unchecked Function</th><th>Safe Function</th>{safety_header}{source_header}</tr></thead><tbody>")?;
+        for finding in findings {
+            let status = finding.safe_counterpart.status();
+            let source = match &mut snippets {
+                Some(snippets) => format!("<td><pre>{}</pre></td>", super::escape_xml(&snippets.get(finding).unwrap_or_default())),
+                None => String::new(),
+            };
+            let safety = match (safety_column, &finding.safety_section) {
+                (true, Some(section)) => format!("<td class=\"safety\">{}</td>", super::escape_xml(section)),
+                (true, None) => "<td></td>".to_string(),
+                (false, _) => String::new(),
+            };
+            writeln!(
+                out,
+                "<tr data-line=\"{line}\" data-name=\"{name}\" data-status=\"{status}\" data-search=\"{search}\"><td>{line}:{column}</td><td title=\"{signature}\">{name}</td><td class=\"{status}\">{counterpart}</td>{safety}{source}</tr>",
+                line = finding.span.line,
+                column = finding.span.column,
+                name = super::escape_xml(&finding.name()),
+                signature = super::escape_xml(finding.signature.as_deref().unwrap_or_default()),
+                search = super::escape_xml(&format!("{} {}", finding.file, finding.qualified_name()).to_lowercase()),
+                counterpart = super::escape_xml(&finding.safe_counterpart.to_string()),
+            )?;
+        }
+        writeln!(out, "</tbody></table>")?;
+        writeln!(out, "</details>")?;
+    }
+    writeln!(out, "</div>")?;
+
+    writeln!(out, "<script>{SCRIPT}</script>")?;
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    Ok(())
+}
+
@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::finding::Finding;
+use crate::scan::FileStats;
+
+use super::snippet::Snippets;
+use super::{ReportOptions, Summary};
+
+// 输出字段有不兼容的变化时递增
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Top-level object of a json report. Findings are sorted by file and
+/// position so that reports of the same tree can be diffed as text.
+#[derive(Serialize, Deserialize)]
+struct Report<F> {
+    schema_version: u32,
+    // 只有扫描时才知道，重新渲染的报告和基线中没有
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    files: Option<FileStats>,
+    // 只是方便阅读，读取时由 findings 重新计算
+    #[serde(default, skip_deserializing)]
+    summary: Summary,
+    findings: Vec<F>,
+}
+
+// 写出时可以附带源码片段，读取时忽略
+#[derive(Serialize)]
+struct FindingWithSnippet<'a> {
+    #[serde(flatten)]
+    finding: &'a Finding,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    snippet: Option<String>,
+}
+
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let mut snippets = options.snippet.map(Snippets::new);
+    let findings = super::sorted(results, options.sort)
+        .into_iter()
+        .map(|finding| FindingWithSnippet {
+            finding,
+            snippet: snippets.as_mut().and_then(|snippets| snippets.get(finding)),
+        })
+        .collect();
+    let report = Report {
+        schema_version: SCHEMA_VERSION,
+        files: options.files,
+        summary: Summary::new(results),
+        findings,
+    };
+    serde_json::to_writer_pretty(&mut *out, &report)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+/// Reads the findings back from a report written in the json format.
+pub fn read(path: &Path) -> Result<HashSet<Finding>> {
+    Ok(read_with_stats(path)?.0)
+}
+
+/// Like [`read`], but also returns the file counts if the report has them.
+pub fn read_with_stats(path: &Path) -> Result<(HashSet<Finding>, Option<FileStats>)> {
+    let file = File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+    let report: Report<Finding> = serde_json::from_reader(BufReader::new(file))
+        .with_context(|| format!("{} is not a json report", path.display()))?;
+    if report.schema_version != SCHEMA_VERSION {
+        bail!(
+            "{} uses report schema version {}, expected {}",
+            path.display(),
+            report.schema_version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok((report.findings.into_iter().collect(), report.files))
+}
@@ -0,0 +1,71 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::finding::Finding;
+
+use super::{escape_xml, ReportOptions};
+
+/// Writes a JUnit XML report with one test suite per file and one test case
+/// per unchecked function. Functions without a safe counterpart are failures,
+/// so CI servers list them in their test result views. Findings of other
+/// kinds are left out.
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let mut by_file: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+    for finding in super::sorted(results, options.sort).into_iter().filter(|finding| finding.is_unchecked_function()) {
+        by_file.entry(finding.file.as_str()).or_default().push(finding);
+    }
+    let tests = by_file.values().map(Vec::len).sum::<usize>();
+    let failures = by_file.values().flatten().filter(|finding| !finding.safe_counterpart.is_safe()).count();
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites name="scan_unchecked" tests="{}" failures="{}">"#,
+        tests,
+        failures
+    )?;
+    for (file, findings) in by_file {
+        let failures = findings.iter().filter(|finding| !finding.safe_counterpart.is_safe()).count();
+        writeln!(
+            out,
+            r#"  <testsuite name="{}" tests="{}" failures="{}">"#,
+            escape_xml(file),
+            findings.len(),
+            failures
+        )?;
+        for finding in findings {
+            let name = escape_xml(&finding.name());
+            let classname = escape_xml(&finding.module_path);
+            let attributes = format!(
+                r#"name="{name}" classname="{classname}" file="{}" line="{}""#,
+                escape_xml(&finding.file),
+                finding.span.line
+            );
+            if finding.safe_counterpart.is_safe() {
+                writeln!(out, "    <testcase {attributes}/>")?;
+                continue;
+            }
+
+            let message = match finding.safe_counterpart.name() {
+                Some(safe_name) => format!("safe counterpart `{safe_name}` is unsafe too"),
+                None => "no safe counterpart".to_string(),
+            };
+            writeln!(out, "    <testcase {attributes}>")?;
+            writeln!(
+                out,
+                r#"      <failure message="{}" type="{}">{}: `{}`: {}</failure>"#,
+                escape_xml(&message),
+                finding.safe_counterpart.status(),
+                escape_xml(&finding.location()),
+                name,
+                escape_xml(&message)
+            )?;
+            writeln!(out, "    </testcase>")?;
+        }
+        writeln!(out, "  </testsuite>")?;
+    }
+    writeln!(out, "</testsuites>")?;
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::finding::{Context, Finding};
+
+use super::{Columns, ReportOptions, Summary};
+
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let columns = Columns::new(results);
+    if options.group {
+        for (crate_name, modules) in super::grouped(results, options.sort) {
+            let summary = Summary::new(modules.values().flatten().copied());
+            writeln!(out, "## `{}`", crate_name)?;
+            writeln!(out)?;
+            writeln!(out, "{}", summary.subtotal())?;
+            for (module_path, findings) in modules {
+                writeln!(out)?;
+                writeln!(out, "### `{}`", module_path)?;
+                writeln!(out)?;
+                writeln!(out, "{}", Summary::new(findings.iter().copied()).subtotal())?;
+                writeln!(out)?;
+                write_table(out, &findings, columns, options)?;
+            }
+            writeln!(out)?;
+        }
+    } else {
+        write_table(out, &super::sorted(results, options.sort), columns, options)?;
+        writeln!(out)?;
+    }
+
+    writeln!(out, "## Summary")?;
+    writeln!(out)?;
+    for line in super::summary_lines(results, options) {
+        writeln!(out, "- {line}")?;
+    }
+
+    // 每个类别的函数假定了什么，帮助读者判断风险
+    let summary = Summary::new(results);
+    if !summary.categories.is_empty() {
+        writeln!(out)?;
+        writeln!(out, "## Categories")?;
+        writeln!(out)?;
+        writeln!(out, "| Category | Functions | The caller must ensure that |")?;
+        writeln!(out, "|---|---|---|")?;
+        for (category, count) in &summary.categories {
+            writeln!(out, "| {} | {} | {} |", category.name(), count, escape(category.invariant()))?;
+        }
+    }
+    Ok(())
+}
+
+fn write_table(out: &mut dyn Write, findings: &[&Finding], columns: Columns, options: &ReportOptions) -> Result<()> {
+    let mut headers = vec!["Location", columns.name_header(), "Safe Function"];
+    if columns.kind {
+        headers.insert(1, "Kind");
+    }
+    if columns.name_matches {
+        headers.insert(headers.len() - 1, "Matches Pattern");
+    }
+    if columns.count {
+        headers.insert(headers.len() - 1, "Count");
+    }
+    if columns.package {
+        headers.insert(0, "Package");
+    } else if columns.root {
+        headers.insert(0, "Root");
+    }
+    writeln!(out, "| {} |", headers.join(" | "))?;
+    writeln!(out, "|{}", "---|".repeat(headers.len()))?;
+
+    for finding in findings {
+        if columns.package {
+            write!(out, "| {} ", escape(&finding.package.as_ref().map(ToString::to_string).unwrap_or_default()))?;
+        } else if columns.root {
+            write!(out, "| {} ", escape(&finding.root))?;
+        }
+        write!(out, "| {} ", escape(&finding.location()))?;
+        if columns.kind {
+            write!(out, "| {} ", finding.kind.name())?;
+        }
+        write!(
+            out,
+            "| `{}`{}{} ",
+            // 分组时模块已经在标题中了
+            if options.group { finding.name() } else { finding.qualified_name() },
+            if finding.deprecated { " (deprecated)" } else { "" },
+            if finding.context == Context::Test { " (test)" } else { "" },
+        )?;
+        if columns.name_matches {
+            write!(out, "| {} ", if finding.name_matches { "yes" } else { "no" })?;
+        }
+        if columns.count {
+            write!(out, "| {} ", finding.count.map(|count| count.to_string()).unwrap_or_default())?;
+        }
+        writeln!(
+            out,
+            "| {} |",
+            match finding.safe_counterpart.name() {
+                Some(_) => format!("`{}`", finding.safe_counterpart),
+                None => "None".to_string(),
+            },
+        )?;
+    }
+    Ok(())
+}
+
+// 单元格中的 `|` 会打断表格
+fn escape(text: &str) -> String {
+    text.replace('|', "\\|")
+}
@@ -0,0 +1,481 @@
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::category::Category;
+use crate::finding::{CounterpartRelation, Finding, FindingKind, SafeCounterpart, SignatureComparison, StabilityLevel};
+use crate::naming::Convention;
+use crate::scan::FileStats;
+
+mod csv;
+mod diagnostics;
+pub mod dot;
+mod html;
+mod json;
+mod junit;
+mod markdown;
+mod modules;
+pub mod ndjson;
+mod sarif;
+mod snippet;
+pub mod sqlite;
+mod table;
+mod template;
+
+pub use json::{read as read_json, read_with_stats as read_json_with_stats};
+
+#[derive(Deserialize, ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    // 对齐的纯文本表格
+    #[default]
+    Table,
+    Json,
+    // 每行一个结果，扫描时边找到边输出；安全版本等只在同一文件中查找
+    Ndjson,
+    Csv,
+    Markdown,
+    // GitHub code scanning 使用的 SARIF 2.1.0
+    Sarif,
+    // 可以直接在浏览器中打开的单文件报告
+    Html,
+    // 每个没有安全版本的函数是一个失败的测试用例
+    Junit,
+    // 写入 SQLite 数据库而不是文本，只能输出到文件
+    Sqlite,
+    // 每个模块一行的汇总表，不列出单个函数
+    Modules,
+    // 调用关系图，需要重新分析调用点，只能在扫描时生成
+    Dot,
+    // 和 rustc 的警告一样的诊断信息，带源码和建议的安全版本签名
+    Diagnostics,
+}
+
+/// Order of the findings in a report.
+#[derive(Deserialize, ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    // 按文件路径，同一文件内按位置
+    #[default]
+    Path,
+    // 按函数名，同名的再按文件路径
+    Name,
+    // 没有安全版本的在前，然后是只有 unsafe 版本的
+    Status,
+}
+
+/// Presentation settings that apply on top of the chosen format.
+#[derive(Debug, Default, Clone)]
+pub struct ReportOptions {
+    // 输出到终端时为表格着色
+    pub color: bool,
+    // 表格和 markdown 按 crate 和模块分组显示
+    pub group: bool,
+    pub sort: SortOrder,
+    // 设置后用这个 Tera 模板代替内置格式
+    pub template: Option<PathBuf>,
+    // json 和 html 中附带定义处的源码，值为定义行之后再取的行数
+    pub snippet: Option<usize>,
+    // 扫描的文件数，重新渲染旧的报告时可能不知道
+    pub files: Option<FileStats>,
+    // 扫描时的命名约定，诊断信息据此给出建议的安全版本的名字；为空时用默认的约定
+    pub conventions: Vec<Convention>,
+}
+
+pub fn write_report(
+    out: &mut dyn Write,
+    format: OutputFormat,
+    results: &HashSet<Finding>,
+    options: &ReportOptions,
+) -> Result<()> {
+    if let Some(template) = &options.template {
+        return template::write(out, template, results, options);
+    }
+    match format {
+        OutputFormat::Table => table::write(out, results, options),
+        OutputFormat::Json => json::write(out, results, options),
+        OutputFormat::Ndjson => ndjson::write(out, results, options),
+        OutputFormat::Csv => csv::write(out, results, options),
+        OutputFormat::Markdown => markdown::write(out, results, options),
+        OutputFormat::Modules => modules::write(out, results, options),
+        OutputFormat::Sarif => sarif::write(out, results, options),
+        OutputFormat::Html => html::write(out, results, options),
+        OutputFormat::Junit => junit::write(out, results, options),
+        OutputFormat::Diagnostics => diagnostics::write(out, results, options),
+        OutputFormat::Sqlite => bail!("the sqlite format needs an output file (--output FILE)"),
+        OutputFormat::Dot => bail!("the dot format is only available when scanning, not with `report` or --watch"),
+    }
+}
+
+// 表格类格式中只在需要时才显示的列
+#[derive(Clone, Copy)]
+struct Columns {
+    // 结果来自多个扫描根目录
+    root: bool,
+    // 用 --workspace 扫描，知道结果所属的包；显示包而不是根目录
+    package: bool,
+    // `--detect unsafe-fn` 找到了名字不匹配 pattern 的函数
+    name_matches: bool,
+    // 有按函数计数的结果，例如 `--detect ptr-deref`
+    count: bool,
+    // 有 static mut、union、unsafe 块等不是 unchecked 函数定义的结果，同一个函数可能有多行，
+    // 名字一列也不全是函数
+    kind: bool,
+}
+
+impl Columns {
+    fn new(results: &HashSet<Finding>) -> Self {
+        let mut roots = results.iter().map(|finding| finding.root.as_str());
+        Columns {
+            root: roots.next().is_some_and(|first| roots.any(|root| root != first)),
+            package: results.iter().any(|finding| finding.package.is_some()),
+            name_matches: results.iter().any(|finding| !finding.name_matches),
+            count: results.iter().any(|finding| finding.count.is_some()),
+            kind: results.iter().any(|finding| !finding.kind.is_unchecked_definition()),
+        }
+    }
+
+    // 名字一列的标题
+    fn name_header(self) -> &'static str {
+        if self.kind {
+            "Item"
+        } else {
+            "Unchecked Function"
+        }
+    }
+}
+
+// 按 crate 和模块分组，组内保持 sorted 的顺序
+fn grouped(results: &HashSet<Finding>, order: SortOrder) -> BTreeMap<&str, BTreeMap<&str, Vec<&Finding>>> {
+    let mut groups: BTreeMap<&str, BTreeMap<&str, Vec<&Finding>>> = BTreeMap::new();
+    for finding in sorted(results, order) {
+        groups.entry(finding.crate_name())
+            .or_default()
+            .entry(finding.module_path.as_str())
+            .or_default()
+            .push(finding);
+    }
+    groups
+}
+
+// 排序后同一份源码的报告可以直接 diff，每种顺序最后都按文件和位置区分
+fn sorted(results: &HashSet<Finding>, order: SortOrder) -> Vec<&Finding> {
+    let mut findings: Vec<&Finding> = results.iter().collect();
+    let by_path = |a: &Finding, b: &Finding| {
+        (&a.file, a.span.line, a.span.column, &a.function).cmp(&(&b.file, b.span.line, b.span.column, &b.function))
+    };
+    match order {
+        SortOrder::Path => findings.sort_by(|a, b| by_path(a, b)),
+        SortOrder::Name => findings.sort_by(|a, b| a.function.cmp(&b.function).then_with(|| by_path(a, b))),
+        SortOrder::Status => findings.sort_by(|a, b| {
+            status_rank(&a.safe_counterpart).cmp(&status_rank(&b.safe_counterpart)).then_with(|| by_path(a, b))
+        }),
+    }
+    findings
+}
+
+fn status_rank(counterpart: &SafeCounterpart) -> u8 {
+    match counterpart {
+        SafeCounterpart::Missing => 0,
+        SafeCounterpart::Unsafe(_) => 1,
+        SafeCounterpart::Unvalidated(_) => 2,
+        SafeCounterpart::Safe(_) => 3,
+    }
+}
+
+/// Counts of findings by the status of their safe counterpart, and the
+/// unsafe blocks counted by `--detect unsafe-metrics`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Summary {
+    pub total: usize,
+    pub safe: usize,
+    #[serde(rename = "unsafe")]
+    pub unsafe_: usize,
+    pub missing: usize,
+    // 有安全版本、但它看起来不做验证的，也计入 safe
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unvalidated: usize,
+    // 按检查种类统计，不包括已弃用的函数
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub categories: BTreeMap<Category, usize>,
+    // 已弃用的函数单独统计，不计入以上各项和覆盖率
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub deprecated: usize,
+    // 不是 unchecked 函数定义的结果按种类统计，例如 static mut 和 unsafe 块，不计入以上各项
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub other_kinds: BTreeMap<FindingKind, usize>,
+    // `--detect unsafe-fn` 找到的名字不匹配 pattern 的 unsafe 函数，同样不计入以上各项
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unmatched_unsafe_fns: usize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unsafe_blocks: usize,
+    // 文档中没有 `# Safety` 一节的 unsafe 函数
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub missing_safety_doc: usize,
+    // 函数体中没有 debug_assert! 等检查的函数
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unchecked_in_debug: usize,
+    // 标准库中稳定的和不稳定的
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub stable: usize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub unstable: usize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub const_fns: usize,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub async_fns: usize,
+    // 带 cfg 属性，只在部分平台或 feature 下编译的
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub conditional: usize,
+    // 与安全版本签名完全相同的函数
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub identical_signatures: usize,
+    // 调用了 unchecked 函数的安全版本
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub wrapping_counterparts: usize,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+impl Summary {
+    pub fn new<'a>(findings: impl IntoIterator<Item = &'a Finding>) -> Self {
+        let mut summary = Summary::default();
+        for finding in findings {
+            if !finding.kind.is_unchecked_definition() {
+                *summary.other_kinds.entry(finding.kind).or_default() += 1;
+            } else if !finding.name_matches {
+                summary.unmatched_unsafe_fns += 1;
+            } else if finding.deprecated {
+                summary.deprecated += 1;
+            } else {
+                summary.total += 1;
+                match finding.safe_counterpart {
+                    SafeCounterpart::Safe(_) => summary.safe += 1,
+                    SafeCounterpart::Unvalidated(_) => {
+                        summary.safe += 1;
+                        summary.unvalidated += 1;
+                    }
+                    SafeCounterpart::Unsafe(_) => summary.unsafe_ += 1,
+                    SafeCounterpart::Missing => summary.missing += 1,
+                }
+                *summary.categories.entry(finding.category).or_default() += 1;
+            }
+            match finding.stability.as_ref().map(|stability| stability.level) {
+                Some(StabilityLevel::Stable) => summary.stable += 1,
+                Some(StabilityLevel::Unstable) => summary.unstable += 1,
+                None => {}
+            }
+            summary.const_fns += usize::from(finding.const_fn);
+            summary.async_fns += usize::from(finding.async_fn);
+            if !finding.cfg.is_empty() {
+                summary.conditional += 1;
+            }
+            if finding.safety_doc == Some(false) {
+                summary.missing_safety_doc += 1;
+            }
+            if finding.debug_checked == Some(false) {
+                summary.unchecked_in_debug += 1;
+            }
+            if finding.counterpart_signature == Some(SignatureComparison::Identical) {
+                summary.identical_signatures += 1;
+            }
+            if finding.counterpart_relation == Some(CounterpartRelation::Wraps) {
+                summary.wrapping_counterparts += 1;
+            }
+            if finding.kind == FindingKind::UnsafeBlocks {
+                summary.unsafe_blocks += finding.count.unwrap_or(0);
+            }
+        }
+        summary
+    }
+
+    // 分组标题中的小计
+    pub fn subtotal(&self) -> String {
+        let subtotal = format!("{} unchecked, {} with a safe version ({:.1}%)", self.total, self.safe, self.coverage());
+        if self.unsafe_blocks > 0 {
+            format!("{subtotal}, {} unsafe blocks", self.unsafe_blocks)
+        } else {
+            subtotal
+        }
+    }
+
+    // 例如 `indexing 40, arithmetic 12`，按类别的顺序
+    pub fn category_breakdown(&self) -> String {
+        self.categories
+            .iter()
+            .map(|(category, count)| format!("{} {}", category.name(), count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // 例如 `unsafe fn (name not matching) 3, static_mut 2, unsafe_blocks 5`，没有这类结果时为空
+    pub fn kind_breakdown(&self) -> String {
+        let unmatched = (self.unmatched_unsafe_fns > 0).then(|| format!("unsafe fn (name not matching) {}", self.unmatched_unsafe_fns));
+        unmatched
+            .into_iter()
+            .chain(self.other_kinds.iter().map(|(kind, count)| format!("{} {}", kind.name(), count)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // 有安全版本的比例，没有结果时为 100%
+    pub fn coverage(&self) -> f64 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.safe as f64 * 100.0 / self.total as f64
+        }
+    }
+}
+
+// html 和 JUnit XML 共用的转义
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// 人类可读格式末尾的汇总信息，每行一项
+fn summary_lines(results: &HashSet<Finding>, options: &ReportOptions) -> Vec<String> {
+    let summary = Summary::new(results);
+    let mut lines = Vec::new();
+    if let Some(files) = options.files {
+        lines.push(format!("Files scanned: {}", files.scanned));
+        lines.push(format!("Files skipped (unreadable or unparseable): {}", files.skipped));
+    }
+    lines.push(format!("Unchecked functions: {}", summary.total));
+    lines.push(format!("With a safe version: {} ({:.1}%)", summary.safe, summary.coverage()));
+    lines.push(format!("With an unsafe version: {}", summary.unsafe_));
+    lines.push(format!("Without a safe version: {}", summary.missing));
+    if summary.unvalidated > 0 {
+        lines.push(format!("Safe versions that appear to perform no validation: {}", summary.unvalidated));
+    }
+    if !summary.categories.is_empty() {
+        lines.push(format!("By category: {}", summary.category_breakdown()));
+    }
+    if summary.deprecated > 0 {
+        lines.push(format!("Deprecated, not counted above: {}", summary.deprecated));
+    }
+    let other_findings = summary.kind_breakdown();
+    if !other_findings.is_empty() {
+        lines.push(format!("Other findings, not counted above: {other_findings}"));
+    }
+    if summary.stable + summary.unstable > 0 {
+        lines.push(format!("Stable: {}, unstable: {}", summary.stable, summary.unstable));
+    }
+    if summary.const_fns + summary.async_fns > 0 {
+        lines.push(format!("const fn: {}, async fn: {}", summary.const_fns, summary.async_fns));
+    }
+    if summary.conditional > 0 {
+        lines.push(format!("Conditionally compiled (cfg): {}", summary.conditional));
+    }
+    let reexported = results.iter().filter(|finding| !finding.reexports.is_empty()).count();
+    if reexported > 0 {
+        lines.push(format!("Re-exported with `pub use`: {reexported}"));
+    }
+    let safe_callers = results.iter().filter(|finding| finding.kind == FindingKind::SafeCaller).count();
+    if safe_callers > 0 {
+        lines.push(format!("Safe pub functions calling unchecked code: {safe_callers}"));
+    }
+    let in_dependencies: Vec<&Finding> =
+        results.iter().filter(|finding| finding.package.as_ref().is_some_and(|package| package.dependency)).collect();
+    if !in_dependencies.is_empty() {
+        let packages: HashSet<String> = in_dependencies.iter().filter_map(|finding| finding.package.as_ref()).map(ToString::to_string).collect();
+        lines.push(format!("In dependencies: {} in {} packages", in_dependencies.len(), packages.len()));
+    }
+    let wrappers: Vec<&Finding> = results.iter().filter(|finding| finding.kind == FindingKind::SafeWrapper).collect();
+    if !wrappers.is_empty() {
+        let blocks: usize = wrappers.iter().map(|finding| finding.count.unwrap_or(0)).sum();
+        lines.push(format!("Safe pub functions with unsafe blocks: {} ({} blocks)", wrappers.len(), blocks));
+    }
+    let documented = results.iter().filter(|finding| finding.safety_doc.is_some()).count();
+    if documented > 0 {
+        lines.push(format!(
+            "Unsafe functions without a `# Safety` section: {} of {}",
+            summary.missing_safety_doc, documented
+        ));
+    }
+    let with_body = results.iter().filter(|finding| finding.debug_checked.is_some()).count();
+    if with_body > 0 {
+        lines.push(format!(
+            "Functions without debug assertions: {} of {}",
+            summary.unchecked_in_debug, with_body
+        ));
+    }
+    if summary.identical_signatures > 0 {
+        lines.push(format!("Counterparts with an identical signature: {}", summary.identical_signatures));
+    }
+    let with_counterpart_body = results
+        .iter()
+        .filter(|finding| matches!(finding.counterpart_relation, Some(CounterpartRelation::Wraps | CounterpartRelation::Independent)))
+        .count();
+    if with_counterpart_body > 0 {
+        lines.push(format!(
+            "Safe versions that call the unchecked function: {} of {}",
+            summary.wrapping_counterparts, with_counterpart_body
+        ));
+    }
+    if summary.unsafe_blocks > 0 {
+        let functions: Vec<&Finding> = results.iter().filter(|finding| finding.kind == FindingKind::UnsafeBlocks).collect();
+        let files: HashSet<&str> = functions.iter().map(|finding| finding.file.as_str()).collect();
+        lines.push(format!(
+            "Unsafe blocks: {} in {} functions across {} files",
+            summary.unsafe_blocks,
+            functions.len(),
+            files.len()
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_counts_only_unchecked_functions() {
+        let mut blocks = Finding::for_test("get", FindingKind::UnsafeBlocks, SafeCounterpart::Missing);
+        blocks.count = Some(3);
+        let findings = [
+            Finding::for_test("get_unchecked", FindingKind::Method, SafeCounterpart::Safe("get".to_string())),
+            Finding::for_test("read_unchecked", FindingKind::Ffi, SafeCounterpart::Missing),
+            Finding { deprecated: true, ..Finding::for_test("old_unchecked", FindingKind::Function, SafeCounterpart::Missing) },
+            Finding { name_matches: false, ..Finding::for_test("read", FindingKind::Function, SafeCounterpart::Missing) },
+            Finding::for_test("COUNTER", FindingKind::StaticMut, SafeCounterpart::Missing),
+            Finding::for_test("peek_unchecked", FindingKind::SafeUnchecked, SafeCounterpart::Missing),
+            blocks,
+        ];
+        let summary = Summary::new(&findings);
+        assert_eq!((summary.total, summary.safe, summary.missing, summary.deprecated), (2, 1, 1, 1));
+        assert_eq!(summary.coverage(), 50.0);
+        assert_eq!(summary.unsafe_blocks, 3);
+        assert_eq!(summary.kind_breakdown(), "unsafe fn (name not matching) 1, safe_unchecked 1, unsafe_blocks 1, static_mut 1");
+    }
+
+    #[test]
+    fn empty_summary_has_full_coverage() {
+        assert_eq!(Summary::new(&[]).coverage(), 100.0);
+    }
+
+    #[test]
+    fn kind_column_only_with_other_kinds() {
+        let method = Finding::for_test("get_unchecked", FindingKind::Method, SafeCounterpart::Missing);
+        let function = Finding::for_test("read_unchecked", FindingKind::Function, SafeCounterpart::Missing);
+        let columns = Columns::new(&HashSet::from([method.clone(), function]));
+        assert!(!columns.kind);
+        assert_eq!(columns.name_header(), "Unchecked Function");
+
+        let static_mut = Finding::for_test("COUNTER", FindingKind::StaticMut, SafeCounterpart::Missing);
+        let columns = Columns::new(&HashSet::from([method, static_mut]));
+        assert!(columns.kind);
+        assert_eq!(columns.name_header(), "Item");
+    }
+}
@@ -0,0 +1,72 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::finding::Finding;
+
+use super::{ReportOptions, SortOrder, Summary};
+
+const HEADERS: [&str; 7] = ["Module", "Unchecked", "Safe", "Unsafe", "Missing", "Coverage", "Unsafe Blocks"];
+
+/// Writes one row per module path with the number of unchecked functions in
+/// it and how many of them have a safe counterpart. Sorting by status puts
+/// the modules with the most functions lacking a safe version first. The
+/// unsafe block column is only shown for `--detect unsafe-metrics` scans.
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let mut by_module: BTreeMap<&str, Vec<&Finding>> = BTreeMap::new();
+    for finding in results {
+        by_module.entry(finding.module_path.as_str()).or_default().push(finding);
+    }
+    let mut modules: Vec<(&str, Summary)> = by_module
+        .into_iter()
+        .map(|(module_path, findings)| (module_path, Summary::new(findings)))
+        .collect();
+    if options.sort == SortOrder::Status {
+        modules.sort_by_key(|(_, summary)| Reverse(summary.total - summary.safe));
+    }
+
+    let rows: Vec<[String; 7]> = modules
+        .iter()
+        .map(|(module_path, summary)| {
+            [
+                module_path.to_string(),
+                summary.total.to_string(),
+                summary.safe.to_string(),
+                summary.unsafe_.to_string(),
+                summary.missing.to_string(),
+                format!("{:.1}%", summary.coverage()),
+                summary.unsafe_blocks.to_string(),
+            ]
+        })
+        .collect();
+    let shown = if modules.iter().any(|(_, summary)| summary.unsafe_blocks > 0) { HEADERS.len() } else { HEADERS.len() - 1 };
+    let widths: Vec<usize> = (0..shown)
+        .map(|column| rows.iter().map(|row| row[column].len()).chain([HEADERS[column].len()]).max().unwrap_or(0))
+        .collect();
+
+    // 模块名左对齐，数字右对齐
+    let format_row = |cells: &[&str]| {
+        let cells: Vec<String> = cells.iter().zip(&widths).enumerate().map(|(column, (cell, width))| {
+            if column == 0 {
+                format!("{cell:<width$}")
+            } else {
+                format!("{cell:>width$}")
+            }
+        }).collect();
+        format!("| {} |", cells.join(" | "))
+    };
+    writeln!(out, "{}", format_row(&HEADERS[..shown]))?;
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+    writeln!(out, "|{}|", separator.join("|"))?;
+    for row in &rows {
+        writeln!(out, "{}", format_row(&row.each_ref().map(String::as_str)[..shown]))?;
+    }
+
+    writeln!(out)?;
+    for line in super::summary_lines(results, options) {
+        writeln!(out, "{line}")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::finding::Finding;
+
+use super::ReportOptions;
+
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    for finding in super::sorted(results, options.sort) {
+        write_line(out, finding)?;
+    }
+    Ok(())
+}
+
+/// Writes one finding as a single line of JSON. Used directly while scanning
+/// so that each finding is written as soon as it is found.
+pub fn write_line(out: &mut dyn Write, finding: &Finding) -> Result<()> {
+    // 整行一次写出，避免多个线程的输出交错
+    let mut line = serde_json::to_string(finding)?;
+    line.push('\n');
+    out.write_all(line.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+
+use crate::finding::{Finding, FindingKind, SafeCounterpart};
+
+use super::ReportOptions;
+
+// (规则 id, 简短描述, 级别)，结果中的 ruleIndex 指向这里的下标
+const RULES: [(&str, &str, &str); 4] = [
+    (
+        "unchecked-without-safe-counterpart",
+        "Unchecked function without a safe counterpart",
+        "warning",
+    ),
+    (
+        "unchecked-with-unsafe-counterpart",
+        "Unchecked function whose counterpart is also unsafe",
+        "note",
+    ),
+    (
+        "safe-counterpart-without-validation",
+        "Safe counterpart that appears to perform no validation",
+        "note",
+    ),
+    (
+        "unchecked-name-without-unsafe",
+        "Function named like an unchecked function that is not unsafe",
+        "warning",
+    ),
+];
+
+/// Writes a SARIF 2.1.0 log for GitHub code scanning. Only unchecked
+/// functions without a safe counterpart, or whose safe counterpart appears to
+/// validate nothing, become results; the others are not problems, and
+/// findings of other kinds have no safe counterpart to look for. Safe
+/// functions with an unchecked name always do.
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let findings: Vec<&Finding> = super::sorted(results, options.sort)
+        .into_iter()
+        .filter(|finding| {
+            finding.kind == FindingKind::SafeUnchecked
+                || finding.is_unchecked_function() && !matches!(finding.safe_counterpart, SafeCounterpart::Safe(_))
+        })
+        .collect();
+
+    let rules: Vec<Value> = RULES.iter().map(|(id, description, level)| {
+        json!({
+            "id": id,
+            "shortDescription": { "text": description },
+            "defaultConfiguration": { "level": level },
+        })
+    }).collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "scan_unchecked",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            // proc_macro2 的列号按字符计算，而不是 SARIF 默认的 UTF-16 码元
+            "columnKind": "unicodeCodePoints",
+            "results": findings.iter().map(|finding| result(finding)).collect::<Vec<_>>(),
+        }],
+    });
+
+    serde_json::to_writer_pretty(&mut *out, &log)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+fn result(finding: &Finding) -> Value {
+    let (rule_index, message) = match &finding.safe_counterpart {
+        _ if finding.kind == FindingKind::SafeUnchecked => {
+            (3, format!("`{}` is named like an unchecked function but is not `unsafe`", finding.name()))
+        }
+        SafeCounterpart::Unsafe(name) => (1, format!("`{}` has a counterpart `{name}`, but it is unsafe too", finding.name())),
+        SafeCounterpart::Unvalidated(name) => (
+            2,
+            format!("`{}` has a safe counterpart `{name}`, but it appears to perform no validation before its unsafe code", finding.name()),
+        ),
+        _ => (0, format!("`{}` has no safe counterpart", finding.name())),
+    };
+    json!({
+        "ruleId": RULES[rule_index].0,
+        "ruleIndex": rule_index,
+        "level": RULES[rule_index].2,
+        "message": { "text": message },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": {
+                    "uri": finding.file.replace('\\', "/"),
+                    "uriBaseId": "%SRCROOT%",
+                },
+                "region": {
+                    "startLine": finding.span.line,
+                    "startColumn": finding.span.column,
+                },
+            },
+            "logicalLocations": [{
+                "fullyQualifiedName": finding.qualified_name(),
+                "kind": "function",
+            }],
+        }],
+    })
+}
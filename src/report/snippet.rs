@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+use std::fs;
+
+use crate::finding::Finding;
+
+/// Source lines at each finding's definition, read lazily and cached per file.
+pub struct Snippets {
+    // 定义所在行之后再取的行数
+    context: usize,
+    // 读取失败的文件记为 None，不再重试
+    sources: HashMap<String, Option<Vec<String>>>,
+}
+
+impl Snippets {
+    pub fn new(context: usize) -> Self {
+        Snippets { context, sources: HashMap::new() }
+    }
+
+    /// The line the function is defined on and `context` lines after it, or
+    /// `None` if the file can no longer be read.
+    pub fn get(&mut self, finding: &Finding) -> Option<String> {
+        let lines = self.sources
+            .entry(finding.file.clone())
+            .or_insert_with(|| fs::read_to_string(&finding.file).ok().map(|content| content.lines().map(str::to_string).collect()))
+            .as_ref()?;
+        let start = finding.span.line.checked_sub(1)?;
+        let end = (start + self.context + 1).min(lines.len());
+        (start < end).then(|| lines[start..end].join("\n"))
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+
+use crate::finding::Finding;
+
+use super::SortOrder;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS scans (
+    id INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    tool_version TEXT NOT NULL,
+    roots TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS files (
+    id INTEGER PRIMARY KEY,
+    scan_id INTEGER NOT NULL REFERENCES scans(id),
+    root TEXT NOT NULL,
+    path TEXT NOT NULL,
+    UNIQUE (scan_id, path)
+);
+CREATE TABLE IF NOT EXISTS findings (
+    id INTEGER PRIMARY KEY,
+    file_id INTEGER NOT NULL REFERENCES files(id),
+    module_path TEXT NOT NULL,
+    function TEXT NOT NULL,
+    owner TEXT,
+    kind TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    column INTEGER NOT NULL,
+    safe_counterpart TEXT,
+    status TEXT NOT NULL
+);
+";
+
+/// Adds the findings to the SQLite database at `path` as a new scan, creating
+/// the database if needed. Earlier scans are kept so the history can be
+/// queried.
+pub fn write(path: &Path, results: &HashSet<Finding>) -> Result<()> {
+    let mut connection = Connection::open(path)
+        .with_context(|| format!("failed to open database {}", path.display()))?;
+    connection.execute_batch(SCHEMA)?;
+    add_owner_column(&connection)?;
+
+    let transaction = connection.transaction()?;
+    let roots: BTreeSet<&str> = results.iter().map(|finding| finding.root.as_str()).collect();
+    transaction.execute(
+        "INSERT INTO scans (timestamp, tool_version, roots) VALUES (datetime('now'), ?1, ?2)",
+        params![env!("CARGO_PKG_VERSION"), roots.into_iter().collect::<Vec<_>>().join("\n")],
+    )?;
+    let scan_id = transaction.last_insert_rowid();
+
+    {
+        let mut insert_file = transaction.prepare("INSERT INTO files (scan_id, root, path) VALUES (?1, ?2, ?3)")?;
+        let mut insert_finding = transaction.prepare(
+            "INSERT INTO findings (file_id, module_path, function, owner, kind, line, column, safe_counterpart, status)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        let mut file_ids: HashMap<&str, i64> = HashMap::new();
+        for finding in super::sorted(results, SortOrder::Path) {
+            let file_id = match file_ids.get(finding.file.as_str()) {
+                Some(&id) => id,
+                None => {
+                    insert_file.execute(params![scan_id, finding.root, finding.file])?;
+                    let id = transaction.last_insert_rowid();
+                    file_ids.insert(&finding.file, id);
+                    id
+                }
+            };
+            insert_finding.execute(params![
+                file_id,
+                finding.module_path,
+                finding.function,
+                finding.owner,
+                finding.kind.name(),
+                finding.span.line,
+                finding.span.column,
+                finding.safe_counterpart.name(),
+                finding.safe_counterpart.status(),
+            ])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+// 较早版本创建的数据库中没有 owner 列
+fn add_owner_column(connection: &Connection) -> Result<()> {
+    let has_owner: bool = connection.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('findings') WHERE name = 'owner'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_owner {
+        connection.execute_batch("ALTER TABLE findings ADD COLUMN owner TEXT")?;
+    }
+    Ok(())
+}
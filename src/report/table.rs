@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+use std::io::Write;
+
+use anyhow::Result;
+use unicode_width::UnicodeWidthStr;
+
+use crate::finding::{Context, Finding, SafeCounterpart};
+
+use super::{Columns, ReportOptions, Summary};
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+pub fn write(out: &mut dyn Write, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let columns = Columns::new(results);
+    if options.group {
+        write_groups(out, results, columns, options)?;
+    } else {
+        write_table(out, &super::sorted(results, options.sort), columns, options)?;
+    }
+
+    writeln!(out)?;
+    for line in super::summary_lines(results, options) {
+        writeln!(out, "{line}")?;
+    }
+    Ok(())
+}
+
+fn write_groups(out: &mut dyn Write, results: &HashSet<Finding>, columns: Columns, options: &ReportOptions) -> Result<()> {
+    for (index, (crate_name, modules)) in super::grouped(results, options.sort).into_iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        let summary = Summary::new(modules.values().flatten().copied());
+        writeln!(out, "{}: {}", paint(crate_name, BOLD, options.color), summary.subtotal())?;
+        for (module_path, findings) in modules {
+            writeln!(out)?;
+            writeln!(out, "{}: {}", module_path, Summary::new(findings.iter().copied()).subtotal())?;
+            write_table(out, &findings, columns, options)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_table(out: &mut dyn Write, findings: &[&Finding], columns: Columns, options: &ReportOptions) -> Result<()> {
+    let mut headers = vec!["Location", columns.name_header(), "Safe Function"];
+    // 分组时模块已经在标题中了
+    let mut rows: Vec<Vec<String>> = findings
+        .iter()
+        .map(|finding| {
+            let mut name = if options.group { finding.name() } else { finding.qualified_name() };
+            if finding.deprecated {
+                name.push_str(" (deprecated)");
+            }
+            if finding.context == Context::Test {
+                name.push_str(" (test)");
+            }
+            vec![finding.location(), name, finding.safe_counterpart.to_string()]
+        })
+        .collect();
+
+    if columns.kind {
+        headers.insert(1, "Kind");
+        for (row, finding) in rows.iter_mut().zip(findings) {
+            row.insert(1, finding.kind.name().to_string());
+        }
+    }
+    // 安全版本保持在最后一列
+    if columns.name_matches {
+        let position = headers.len() - 1;
+        headers.insert(position, "Matches Pattern");
+        for (row, finding) in rows.iter_mut().zip(findings) {
+            row.insert(position, if finding.name_matches { "yes" } else { "no" }.to_string());
+        }
+    }
+    if columns.count {
+        let position = headers.len() - 1;
+        headers.insert(position, "Count");
+        for (row, finding) in rows.iter_mut().zip(findings) {
+            row.insert(position, finding.count.map(|count| count.to_string()).unwrap_or_default());
+        }
+    }
+    // 扫描了多个根目录时才显示根目录列，知道所属的包时显示包
+    if columns.package {
+        headers.insert(0, "Package");
+        for (row, finding) in rows.iter_mut().zip(findings) {
+            row.insert(0, finding.package.as_ref().map(ToString::to_string).unwrap_or_default());
+        }
+    } else if columns.root {
+        headers.insert(0, "Root");
+        for (row, finding) in rows.iter_mut().zip(findings) {
+            row.insert(0, finding.root.clone());
+        }
+    }
+
+    // 按显示宽度而不是字节数计算列宽，路径中可能有中文等宽字符
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].width())
+                .chain([headers[column].width()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let header: Vec<String> = headers.iter().zip(&widths).map(|(name, width)| {
+        paint(&pad(name, *width), BOLD, options.color)
+    }).collect();
+    writeln!(out, "| {} |", header.join(" | "))?;
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+    writeln!(out, "|{}|", separator.join("|"))?;
+
+    for (row, finding) in rows.iter().zip(findings) {
+        let color = match finding.safe_counterpart {
+            SafeCounterpart::Safe(_) => GREEN,
+            SafeCounterpart::Unvalidated(_) | SafeCounterpart::Unsafe(_) => YELLOW,
+            SafeCounterpart::Missing => RED,
+        };
+        let last = row.len() - 1;
+        let cells: Vec<String> = row.iter().zip(&widths).enumerate().map(|(column, (cell, width))| {
+            let cell = pad(cell, *width);
+            // 只给安全版本这一列着色
+            if column == last {
+                paint(&cell, color, options.color)
+            } else {
+                cell
+            }
+        }).collect();
+        writeln!(out, "| {} |", cells.join(" | "))?; // 写入结果
+    }
+
+    Ok(())
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!("{text}{}", " ".repeat(width.saturating_sub(text.width())))
+}
+
+pub(super) fn paint(text: &str, style: &str, color: bool) -> String {
+    if color {
+        format!("{style}{text}{RESET}")
+    } else {
+        text.to_string()
+    }
+}
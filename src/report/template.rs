@@ -0,0 +1,40 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tera::Tera;
+
+use crate::finding::Finding;
+use crate::scan::FileStats;
+
+use super::{ReportOptions, Summary};
+
+// 模板中可用的变量，findings 中每一项的字段和 json 格式相同
+#[derive(Serialize)]
+struct TemplateContext<'a> {
+    findings: Vec<&'a Finding>,
+    summary: Summary,
+    files: Option<FileStats>,
+}
+
+/// Renders the findings with a user-supplied Tera template. The template sees
+/// `findings` (in report order, with the same fields as the json format),
+/// `summary` and, when known, `files`.
+pub fn write(out: &mut dyn Write, template: &Path, results: &HashSet<Finding>, options: &ReportOptions) -> Result<()> {
+    let source = fs::read_to_string(template)
+        .with_context(|| format!("failed to read template {}", template.display()))?;
+    let context = TemplateContext {
+        findings: super::sorted(results, options.sort),
+        summary: Summary::new(results),
+        files: options.files,
+    };
+    let context = tera::Context::from_serialize(context)?;
+    // 不做 html 转义，模板的输出格式由用户决定
+    let rendered = Tera::one_off(&source, &context, false)
+        .with_context(|| format!("failed to render template {}", template.display()))?;
+    out.write_all(rendered.as_bytes())?;
+    Ok(())
+}
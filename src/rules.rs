@@ -0,0 +1,156 @@
+use regex::Regex;
+
+use crate::finding::FindingKind;
+use crate::scan::Detect;
+
+// 从裸指针构造切片或字符串的函数，查找调用时不管 pattern 都会报告
+const RAW_CONSTRUCTORS: [&str; 4] = ["from_raw_parts", "from_raw_parts_mut", "from_utf8_unchecked", "from_utf8_unchecked_mut"];
+
+/// Decides which function definitions and call sites a scan reports. The
+/// scan asks every selected rule and reports what any of them accepts.
+pub trait Rule: Send + Sync {
+    /// Whether a function, method or foreign function definition is reported.
+    fn check_fn(&self, _name: &str, _is_unsafe: bool) -> bool {
+        false
+    }
+
+    /// What a definition this rule accepts is reported as, given what kind
+    /// of item the scan found it as.
+    fn fn_kind(&self, kind: FindingKind) -> FindingKind {
+        kind
+    }
+
+    /// What a call to `callee` is reported as, if it is reported at all.
+    /// `path` holds the segments of `path::to::callee(..)`, with names
+    /// imported by `use` expanded, and is `None` for method calls.
+    fn check_call(&self, _callee: &str, _path: Option<&[String]>) -> Option<FindingKind> {
+        None
+    }
+}
+
+/// Functions whose name matches the pattern.
+pub struct UncheckedName {
+    pub pattern: Regex,
+}
+
+impl Rule for UncheckedName {
+    fn check_fn(&self, name: &str, _is_unsafe: bool) -> bool {
+        self.pattern.is_match(name)
+    }
+}
+
+/// Every `unsafe fn`, whatever its name.
+pub struct UnsafeFn;
+
+impl Rule for UnsafeFn {
+    fn check_fn(&self, _name: &str, is_unsafe: bool) -> bool {
+        is_unsafe
+    }
+}
+
+/// Functions whose name matches the pattern but that are not `unsafe`.
+pub struct SafeUnchecked {
+    pub pattern: Regex,
+}
+
+impl Rule for SafeUnchecked {
+    fn check_fn(&self, name: &str, is_unsafe: bool) -> bool {
+        !is_unsafe && self.pattern.is_match(name)
+    }
+
+    fn fn_kind(&self, _kind: FindingKind) -> FindingKind {
+        FindingKind::SafeUnchecked
+    }
+}
+
+/// Calls to functions whose name matches the pattern, and to raw slice and
+/// str constructors.
+pub struct CallSites {
+    pub pattern: Regex,
+}
+
+impl Rule for CallSites {
+    fn check_call(&self, callee: &str, path: Option<&[String]>) -> Option<FindingKind> {
+        if path.is_some() && RAW_CONSTRUCTORS.contains(&callee) {
+            Some(FindingKind::RawConstruction)
+        } else if self.pattern.is_match(callee) {
+            Some(FindingKind::Call)
+        } else {
+            None
+        }
+    }
+}
+
+/// Calls to `mem::transmute` and `mem::transmute_copy`.
+pub struct Transmute;
+
+impl Rule for Transmute {
+    fn check_call(&self, callee: &str, path: Option<&[String]>) -> Option<FindingKind> {
+        (path.is_some() && (callee == "transmute" || callee == "transmute_copy")).then_some(FindingKind::Transmute)
+    }
+}
+
+/// Calls to functions in `core::intrinsics` or `std::intrinsics`, which skip
+/// even the checks of the `_unchecked` methods built on them.
+pub struct Intrinsics;
+
+impl Rule for Intrinsics {
+    fn check_call(&self, _callee: &str, path: Option<&[String]>) -> Option<FindingKind> {
+        // `intrinsics::f`、`core::intrinsics::f`、`crate::intrinsics::simd::f` 等
+        let (_, modules) = path?.split_last()?;
+        modules.iter().any(|module| module == "intrinsics").then_some(FindingKind::Intrinsic)
+    }
+}
+
+/// The rules behind the selected `--detect` names. Rules that look at the
+/// structure of the code rather than at definitions and calls (unsafe blocks,
+/// unions and so on) are handled by the scan itself and have no `Rule`.
+pub fn rules(detect: &[Detect], pattern: &Regex) -> Vec<Box<dyn Rule>> {
+    // 第一个接受定义或调用的规则决定它的种类，所以 intrinsics 排在 calls 前面，
+    // safe-unchecked 排在 name 前面
+    let first = |detect: &&Detect| matches!(detect, Detect::Intrinsics | Detect::SafeUnchecked);
+    detect
+        .iter()
+        .filter(first)
+        .chain(detect.iter().filter(|detect| !first(detect)))
+        .filter_map(|detect| -> Option<Box<dyn Rule>> {
+            match detect {
+                Detect::Name => Some(Box::new(UncheckedName { pattern: pattern.clone() })),
+                Detect::UnsafeFn => Some(Box::new(UnsafeFn)),
+                Detect::SafeUnchecked => Some(Box::new(SafeUnchecked { pattern: pattern.clone() })),
+                Detect::Calls => Some(Box::new(CallSites { pattern: pattern.clone() })),
+                Detect::Transmute => Some(Box::new(Transmute)),
+                Detect::Intrinsics => Some(Box::new(Intrinsics)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind_of(detect: &[Detect], name: &str, is_unsafe: bool) -> Option<FindingKind> {
+        let pattern = Regex::new("unchecked").unwrap();
+        let rules = rules(detect, &pattern);
+        rules.iter().find(|rule| rule.check_fn(name, is_unsafe)).map(|rule| rule.fn_kind(FindingKind::Method))
+    }
+
+    #[test]
+    fn safe_unchecked_has_its_own_kind() {
+        let detect = [Detect::Name, Detect::SafeUnchecked];
+        assert_eq!(kind_of(&detect, "get_unchecked", false), Some(FindingKind::SafeUnchecked));
+        assert_eq!(kind_of(&detect, "get_unchecked", true), Some(FindingKind::Method));
+        assert_eq!(kind_of(&[Detect::SafeUnchecked], "get_unchecked", true), None);
+    }
+
+    #[test]
+    fn intrinsics_take_precedence_over_calls() {
+        let pattern = Regex::new("unchecked").unwrap();
+        let rules = rules(&[Detect::Calls, Detect::Intrinsics], &pattern);
+        let path = ["intrinsics".to_string(), "unchecked_add".to_string()];
+        let kind = rules.iter().find_map(|rule| rule.check_call("unchecked_add", Some(&path)));
+        assert_eq!(kind, Some(FindingKind::Intrinsic));
+    }
+}
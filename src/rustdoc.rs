@@ -0,0 +1,298 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context as _, Result};
+use serde_json::{Map, Value};
+
+use crate::category;
+use crate::config::Settings;
+use crate::finding::{Context, Finding, FindingKind, SafeCounterpart, Span};
+use crate::naming;
+use crate::rules;
+use crate::scan::{self, FileStats, ScanResults, VisibilityFilter};
+
+/// Reads the functions documented in the rustdoc JSON file at `path` and
+/// reports those the `--detect` rules select, like [`scan::scan`] does for
+/// source files. The safe counterpart is looked up among the functions of
+/// the same type, or the free functions of the crate, by name alone.
+pub fn scan(settings: &Settings, path: &Path) -> Result<ScanResults> {
+    scan_each(settings, path, |_| Ok(()))
+}
+
+/// Like [`scan`], but also passes each finding to `on_finding`.
+pub fn scan_each(settings: &Settings, path: &Path, on_finding: impl Fn(&Finding) -> Result<()>) -> Result<ScanResults> {
+    let content = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let krate: Value = serde_json::from_str(&content).with_context(|| format!("{} is not valid JSON", path.display()))?;
+    let (Some(index), Some(root)) = (krate["index"].as_object(), krate.get("root")) else {
+        bail!("{} is not rustdoc JSON output", path.display());
+    };
+    // 格式版本 25 之前的项用 `kind` 字段区分种类，结构也不同
+    if index.values().next().is_some_and(|item| item.get("kind").is_some()) {
+        bail!("{} uses rustdoc JSON format version {}, which is too old; use a newer nightly", path.display(), krate["format_version"]);
+    }
+
+    let mut walker = Walker { index, seen_impls: HashSet::new(), functions: Vec::new() };
+    let Some(root) = lookup(index, root) else {
+        bail!("the root module of {} is missing from its index", path.display());
+    };
+    walker.walk_module(root, &name(root));
+    log::info!("{}: {} functions", path.display(), walker.functions.len());
+
+    // 所属类型或 trait 和函数名相同的函数
+    let mut by_name: HashMap<(Option<String>, String), Vec<&Function>> = HashMap::new();
+    for function in &walker.functions {
+        by_name.entry((function.owner.clone(), function.name.clone())).or_default().push(function);
+    }
+    let rules = rules::rules(&settings.detect, &settings.pattern);
+    let root = path.display().to_string();
+    let mut findings = HashSet::new();
+    for function in &walker.functions {
+        let Some(rule) = rules.iter().find(|rule| rule.check_fn(&function.name, function.is_unsafe)) else {
+            continue;
+        };
+        if !accepts(settings.visibility, function.visibility)
+            || (settings.only_const && !function.is_const)
+            || (settings.only_async && !function.is_async)
+        {
+            continue;
+        }
+        let finding = Finding { kind: rule.fn_kind(function.kind), ..finding(settings, &root, function, &by_name) };
+        on_finding(&finding)?;
+        findings.insert(finding);
+    }
+    Ok(ScanResults { findings, files: FileStats { scanned: 1, skipped: 0 } })
+}
+
+// 函数的可见性；trait 中的方法与 trait 相同，trait 实现中的方法算作公开
+#[derive(Clone, Copy)]
+enum Visibility {
+    Public,
+    Crate,
+    Private,
+}
+
+fn accepts(filter: VisibilityFilter, visibility: Visibility) -> bool {
+    matches!(
+        (filter, visibility),
+        (VisibilityFilter::All, _) | (_, Visibility::Public) | (VisibilityFilter::PubCrate, Visibility::Crate)
+    )
+}
+
+// rustdoc 索引中的一个函数，以及它在模块树中的位置
+struct Function<'a> {
+    item: &'a Value,
+    name: String,
+    module_path: String,
+    owner: Option<String>,
+    trait_name: Option<String>,
+    kind: FindingKind,
+    visibility: Visibility,
+    is_unsafe: bool,
+    is_const: bool,
+    is_async: bool,
+}
+
+// 从根模块开始遍历，记录每个函数所在的模块路径；
+// impl 块从类型的 impls 和 trait 的 implementations 中找到，同一个只看一次
+struct Walker<'a> {
+    index: &'a Map<String, Value>,
+    seen_impls: HashSet<String>,
+    functions: Vec<Function<'a>>,
+}
+
+impl<'a> Walker<'a> {
+    fn walk_module(&mut self, module: &'a Value, module_path: &str) {
+        let index = self.index;
+        for item in ids(&inner(module, "module")["items"]).filter_map(|id| lookup(index, id)) {
+            let Some((kind, details)) = item["inner"].as_object().and_then(|inner| inner.iter().next()) else {
+                continue;
+            };
+            match kind.as_str() {
+                "module" => self.walk_module(item, &format!("{}::{}", module_path, name(item))),
+                "function" => self.add(item, module_path, None, None, FindingKind::Function, visibility(item)),
+                "struct" | "enum" | "union" => {
+                    for implementation in ids(&details["impls"]).filter_map(|id| lookup(index, id)) {
+                        self.walk_impl(implementation, module_path);
+                    }
+                }
+                "trait" => {
+                    let trait_name = name(item);
+                    for method in ids(&details["items"]).filter_map(|id| lookup(index, id)) {
+                        let owner = Some(trait_name.clone());
+                        self.add(method, module_path, owner, None, FindingKind::TraitMethod, visibility(item));
+                    }
+                    for implementation in ids(&details["implementations"]).filter_map(|id| lookup(index, id)) {
+                        self.walk_impl(implementation, module_path);
+                    }
+                }
+                // 较早的格式中 impl 块也列在模块中
+                "impl" => self.walk_impl(item, module_path),
+                _ => {}
+            }
+        }
+    }
+
+    // 编译器生成的和 `impl<T> Trait for T` 这样的泛型实现不算
+    fn walk_impl(&mut self, item: &'a Value, module_path: &str) {
+        let id = match &item["id"] {
+            Value::String(id) => id.clone(),
+            id => id.to_string(),
+        };
+        let index = self.index;
+        let details = inner(item, "impl");
+        if !self.seen_impls.insert(id) || details["is_synthetic"] == true || !details["blanket_impl"].is_null() {
+            return;
+        }
+        let owner = type_name(&details["for"]);
+        let trait_name = details["trait"]["path"].as_str().map(last_segment);
+        for method in ids(&details["items"]).filter_map(|id| lookup(index, id)) {
+            let visibility = if trait_name.is_some() { Visibility::Public } else { visibility(method) };
+            self.add(method, module_path, Some(owner.clone()), trait_name.clone(), FindingKind::Method, visibility);
+        }
+    }
+
+    fn add(
+        &mut self,
+        item: &'a Value,
+        module_path: &str,
+        owner: Option<String>,
+        trait_name: Option<String>,
+        kind: FindingKind,
+        visibility: Visibility,
+    ) {
+        let function = inner(item, "function");
+        if function.is_null() {
+            return;
+        }
+        // 格式版本 34 之前是 `unsafe_`、`const_`、`async_`
+        let header = &function["header"];
+        let flag = |name: &str| header[name] == true || header[name.trim_start_matches("is_").to_string() + "_"] == true;
+        self.functions.push(Function {
+            item,
+            name: name(item),
+            module_path: module_path.to_string(),
+            owner,
+            trait_name,
+            kind,
+            visibility,
+            is_unsafe: flag("is_unsafe"),
+            is_const: flag("is_const"),
+            is_async: flag("is_async"),
+        });
+    }
+}
+
+fn finding(
+    settings: &Settings,
+    root: &str,
+    function: &Function,
+    by_name: &HashMap<(Option<String>, String), Vec<&Function>>,
+) -> Finding {
+    // 同名的函数中优先同一模块中的，其次安全的
+    let found: Vec<(String, &Function)> = naming::safe_name_candidates(&settings.conventions, &function.name)
+        .into_iter()
+        .filter_map(|safe_name| {
+            let candidates = by_name.get(&(function.owner.clone(), safe_name.clone()))?;
+            let closest = candidates
+                .iter()
+                .max_by_key(|candidate| (candidate.module_path == function.module_path, !candidate.is_unsafe))?;
+            Some((safe_name, *closest))
+        })
+        .collect();
+    let counterpart = found.iter().find(|(_, candidate)| !candidate.is_unsafe).or(found.first());
+    let safe_counterpart = match counterpart {
+        Some((safe_name, candidate)) if !candidate.is_unsafe => SafeCounterpart::Safe(safe_name.clone()),
+        Some((safe_name, _)) => SafeCounterpart::Unsafe(safe_name.clone()),
+        None => SafeCounterpart::Missing,
+    };
+    let section = function.item["docs"].as_str().and_then(scan::doc_safety_section);
+    let span = &function.item["span"];
+    let position = |index: usize| span["begin"][index].as_u64().unwrap_or(0) as usize;
+    Finding {
+        root: root.to_string(),
+        package: None,
+        file: span["filename"].as_str().unwrap_or_default().to_string(),
+        module_path: function.module_path.clone(),
+        function: function.name.clone(),
+        name_matches: settings.pattern.is_match(&function.name),
+        owner: function.owner.clone(),
+        trait_name: function.trait_name.clone(),
+        signature: None,
+        cfg: Vec::new(),
+        features: Vec::new(),
+        deprecated: !function.item["deprecation"].is_null(),
+        const_fn: function.is_const,
+        async_fn: function.is_async,
+        stability: None,
+        safety_doc: function.is_unsafe.then(|| section.is_some()),
+        safety_section: section.filter(|text| !text.is_empty()),
+        debug_checked: None,
+        counterpart_signature: None,
+        counterpart_relation: None,
+        added_checks: Vec::new(),
+        caller: None,
+        kind: function.kind,
+        context: Context::Code,
+        category: category::classify(&function.name, function.owner.as_deref()),
+        span: Span { line: position(0), column: position(1) },
+        count: None,
+        call_chain: Vec::new(),
+        example_calls: Vec::new(),
+        callers: Vec::new(),
+        reexports: Vec::new(),
+        safe_counterpart,
+    }
+}
+
+// 格式版本 36 之前的 id 是字符串，之后是整数
+fn lookup<'a>(index: &'a Map<String, Value>, id: &Value) -> Option<&'a Value> {
+    match id {
+        Value::String(id) => index.get(id),
+        id => index.get(&id.to_string()),
+    }
+}
+
+// 项的 inner 中给定种类的内容，种类不同时为 Null
+fn inner<'a>(item: &'a Value, kind: &str) -> &'a Value {
+    &item["inner"][kind]
+}
+
+fn ids(list: &Value) -> impl Iterator<Item = &Value> {
+    list.as_array().into_iter().flatten()
+}
+
+fn name(item: &Value) -> String {
+    item["name"].as_str().unwrap_or_default().to_string()
+}
+
+fn visibility(item: &Value) -> Visibility {
+    match &item["visibility"] {
+        Value::String(visibility) if visibility == "public" => Visibility::Public,
+        Value::String(visibility) if visibility == "crate" => Visibility::Crate,
+        _ => Visibility::Private,
+    }
+}
+
+// 较早的格式中路径是完整的，例如 `vec::Vec`
+fn last_segment(path: &str) -> String {
+    path.rsplit("::").next().unwrap_or(path).to_string()
+}
+
+// impl 的类型名，与 scan::type_name 从源码得到的一样，不带泛型参数
+fn type_name(ty: &Value) -> String {
+    let Some((kind, details)) = ty.as_object().and_then(|ty| ty.iter().next()) else {
+        return String::new();
+    };
+    // 格式版本 34 之前是 `mutable`
+    let is_mutable = details["is_mutable"] == true || details["mutable"] == true;
+    match kind.as_str() {
+        "resolved_path" => details["path"].as_str().map(last_segment).unwrap_or_default(),
+        "primitive" | "generic" => details.as_str().unwrap_or_default().to_string(),
+        "slice" => format!("[{}]", type_name(details)),
+        "array" => format!("[{}; _]", type_name(&details["type"])),
+        "raw_pointer" => format!("*{} {}", if is_mutable { "mut" } else { "const" }, type_name(&details["type"])),
+        "borrowed_ref" => format!("&{}{}", if is_mutable { "mut " } else { "" }, type_name(&details["type"])),
+        _ => kind.clone(),
+    }
+}
@@ -0,0 +1,1931 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use quote::ToTokens;
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use syn::{Attribute, Block, Expr, ExprAssign, ExprCall, ExprField, ExprMethodCall, ExprUnary, ExprUnsafe, UnOp, ForeignItem, ForeignItemFn, Ident, ImplItemFn, ItemFn, ItemForeignMod, TraitItemFn, ItemImpl, ItemMacro, ItemMod, ItemStatic, ItemStruct, ItemEnum, ItemTrait, ItemUnion, GenericArgument, Macro, Member, PathArguments, Signature, StaticMutability, Type, visit::{self, Visit}, parse_file, ImplItem, TraitItem, ItemUse, UseTree};
+
+use crate::category::{self, Category};
+use crate::cfg;
+use crate::checks::{self, BodyChecks, Check};
+use crate::config::Settings;
+use crate::doctest;
+use crate::files::{self, SourceFile};
+use crate::finding::{Caller, Context, CounterpartRelation, Finding, FindingKind, SafeCounterpart, Span, Stability, StabilityLevel};
+use crate::naming;
+use crate::propagation::{self, CallNode, Callee};
+use crate::rules::{self, Rule};
+use crate::signature::SignatureShape;
+
+// 假定 MaybeUninit 已经初始化的方法，`--detect assume-init` 按函数统计对它们的调用
+const ASSUME_INIT: [&str; 3] = ["assume_init", "assume_init_ref", "assume_init_mut"];
+
+/// A detection rule, selected by name with `--detect`/`--rules`. A scan
+/// reports what any of its rules find.
+#[derive(Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Detect {
+    /// Functions whose name matches --pattern
+    #[value(alias = "unchecked-name")]
+    #[serde(alias = "unchecked-name")]
+    Name,
+    /// Every `unsafe fn`, whatever its name
+    UnsafeFn,
+    /// Functions matching --pattern that are not `unsafe fn`: misnamed, or
+    /// missing a safety contract
+    SafeUnchecked,
+    /// Calls to matching functions and to raw slice and str constructors
+    #[value(alias = "call-sites")]
+    #[serde(alias = "call-sites")]
+    Calls,
+    /// Calls to `mem::transmute` and `mem::transmute_copy`
+    Transmute,
+    /// Calls into `core::intrinsics` and `std::intrinsics`, by path or through
+    /// a `use`
+    Intrinsics,
+    /// Functions dereferencing values in unsafe code, with the count
+    #[value(alias = "raw-ptr")]
+    #[serde(alias = "raw-ptr")]
+    PtrDeref,
+    /// The number of unsafe blocks in each function, file and crate
+    UnsafeMetrics,
+    /// Examples in doc comments that call functions matching --pattern or
+    /// use `unsafe` without a `// SAFETY:` comment or an assertion stating
+    /// the preconditions
+    #[value(alias = "doctests")]
+    #[serde(alias = "doctests")]
+    DocExamples,
+    /// Functions calling `MaybeUninit::assume_init`, `assume_init_ref` or
+    /// `assume_init_mut`, with the count
+    AssumeInit,
+    /// `#[no_mangle]` and `#[export_name]` functions that are unsafe or match --pattern
+    Exported,
+    /// `unsafe impl Trait for Type`, such as manual `Send` and `Sync`
+    UnsafeImpl,
+    /// Union definitions and reads of union fields
+    Unions,
+    /// `static mut` declarations
+    StaticMut,
+    /// Safe `pub` functions that call functions matching --pattern, directly
+    /// or through other functions of the same crate, with the call chain
+    #[value(alias = "transitive")]
+    #[serde(alias = "transitive")]
+    Propagation,
+    /// Safe `pub` functions whose bodies contain `unsafe` blocks, with the
+    /// count: where unsafe code is wrapped in a safe API
+    #[value(alias = "encapsulation")]
+    #[serde(alias = "encapsulation")]
+    SafeWrappers,
+}
+
+/// Which definitions are reported, by their visibility as written. Trait
+/// methods have the visibility of their trait and methods of trait impls are
+/// taken as public; whether the enclosing modules are public is not checked.
+#[derive(Deserialize, ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum VisibilityFilter {
+    /// Every definition
+    #[default]
+    All,
+    /// Only `pub` definitions
+    Pub,
+    /// `pub` and `pub(crate)` definitions
+    PubCrate,
+}
+
+impl VisibilityFilter {
+    fn accepts(self, vis: &syn::Visibility) -> bool {
+        match (self, vis) {
+            (VisibilityFilter::All, _) | (_, syn::Visibility::Public(_)) => true,
+            (VisibilityFilter::PubCrate, syn::Visibility::Restricted(restricted)) => restricted.path.is_ident("crate"),
+            _ => false,
+        }
+    }
+}
+
+/// How many of the given files were scanned. Files that cannot be read or
+/// parsed are skipped with a warning instead of failing the whole scan.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileStats {
+    pub scanned: usize,
+    pub skipped: usize,
+}
+
+pub struct ScanResults {
+    pub findings: HashSet<Finding>,
+    pub files: FileStats,
+}
+
+/// Parses `files` in parallel and returns every unchecked function found,
+/// together with its safe counterpart lookup.
+pub fn scan(settings: &Settings, files: &[SourceFile]) -> Result<ScanResults> {
+    let (parsed, stats) = parse_files(settings, files);
+    let findings = resolve(settings, &parsed, |_| Ok(()))?;
+    Ok(ScanResults { findings, files: stats })
+}
+
+/// Like [`scan`], but also passes each finding to `on_finding` as soon as the
+/// file it is in has been processed, in no particular order, without keeping
+/// the parsed files around. Each file is resolved on its own, so safe
+/// counterparts, callers, re-exports and propagation are only looked up
+/// within the same file.
+pub fn scan_each(
+    settings: &Settings,
+    files: &[SourceFile],
+    on_finding: impl Fn(&Finding) -> Result<()> + Sync,
+) -> Result<ScanResults> {
+    log::info!("Streaming results: safe counterparts are only looked up in the same file");
+    let results = Mutex::new(HashSet::new());
+    let skipped = AtomicUsize::new(0);
+
+    files.par_iter().try_for_each(|file| -> Result<()> {
+        log::debug!("Processing file: {}", file.path);
+        let parsed = match process_file(file, settings) { // 处理 Rust 文件
+            Ok(parsed) => parsed,
+            Err(err) => {
+                log::warn!("Skipping {}: {}", file.path, err);
+                skipped.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+        };
+        let findings = resolve(settings, [&parsed], &on_finding)?;
+        results.lock().unwrap().extend(findings);
+        Ok(())
+    })?;
+
+    let skipped = skipped.into_inner();
+    Ok(ScanResults {
+        findings: results.into_inner().unwrap(),
+        files: FileStats { scanned: files.len() - skipped, skipped },
+    })
+}
+
+/// What was found in one file, before safe counterparts are looked up.
+pub struct ParsedFile {
+    pub file: SourceFile,
+    module_path: String,
+    unchecked_functions: Vec<UncheckedFn>,
+    definitions: HashMap<DefinitionKey, Definition>,
+    // 只在检测 propagation 时收集
+    call_nodes: Vec<CallerFn>,
+    // 只在用 `--callers` 扫描时收集
+    call_sites: Vec<CallSite>,
+    re_exports: Vec<ReExport>,
+    test_file: bool,
+}
+
+/// Parses `files` in parallel. Files that cannot be read or parsed are
+/// skipped with a warning and counted in the returned stats.
+pub fn parse_files(settings: &Settings, files: &[SourceFile]) -> (Vec<ParsedFile>, FileStats) {
+    let skipped = AtomicUsize::new(0);
+    let parsed: Vec<ParsedFile> = files
+        .par_iter()
+        .filter_map(|file| {
+            log::debug!("Processing file: {}", file.path);
+            match process_file(file, settings) { // 处理 Rust 文件
+                Ok(parsed) => Some(parsed),
+                Err(err) => {
+                    log::warn!("Skipping {}: {}", file.path, err);
+                    skipped.fetch_add(1, Ordering::Relaxed);
+                    None
+                }
+            }
+        })
+        .collect();
+
+    let skipped = skipped.into_inner();
+    (parsed, FileStats { scanned: files.len() - skipped, skipped })
+}
+
+/// Looks up the safe counterpart of everything found in `parsed`: first in
+/// the same file, then in the closest module of the same crate that defines
+/// a function of that name. Methods only match methods of the same type or
+/// trait, in any of its impl blocks; calls, whose receiver type is unknown,
+/// match any function of that name.
+pub fn resolve<'a>(
+    settings: &Settings,
+    parsed: impl IntoIterator<Item = &'a ParsedFile>,
+    on_finding: impl Fn(&Finding) -> Result<()> + Sync,
+) -> Result<HashSet<Finding>> {
+    let parsed: Vec<&ParsedFile> = parsed.into_iter().collect();
+    let mut index: HashMap<&str, Vec<IndexEntry>> = HashMap::new();
+    for parsed_file in &parsed {
+        for ((owner, name), definition) in &parsed_file.definitions {
+            index.entry(name.as_str()).or_default().push(IndexEntry {
+                file: &parsed_file.file.path,
+                module_path: &parsed_file.module_path,
+                owner: owner.as_deref(),
+                definition,
+            });
+        }
+    }
+
+    let safe_callers = if settings.detect.contains(&Detect::Propagation) {
+        safe_callers(settings, &parsed)
+    } else {
+        HashMap::new()
+    };
+
+    let mut call_sites: HashMap<&str, Vec<(&ParsedFile, &CallSite)>> = HashMap::new();
+    for parsed_file in &parsed {
+        for call_site in &parsed_file.call_sites {
+            call_sites.entry(call_site.callee.name.as_str()).or_default().push((*parsed_file, call_site));
+        }
+    }
+
+    let re_exports = re_export_index(&parsed);
+
+    let results = Mutex::new(HashSet::new());
+    parsed.par_iter().try_for_each(|parsed_file| -> Result<()> {
+        let callers = safe_callers.get(parsed_file.file.path.as_str()).map(Vec::as_slice).unwrap_or_default();
+        let mut findings = check_for_safe_versions(settings, parsed_file, callers, &index);
+        if settings.callers {
+            for finding in findings.iter_mut().filter(|finding| finding.kind.is_function()) {
+                finding.callers = callers_of(finding, call_sites.get(finding.function.as_str()).map(Vec::as_slice).unwrap_or_default());
+            }
+        }
+        // `use` 只能导出自由函数，不能导出方法
+        for finding in findings.iter_mut().filter(|finding| finding.kind.is_function() && finding.owner.is_none()) {
+            if let Some(paths) = re_exports.get(&(finding.crate_name(), finding.function.as_str())) {
+                finding.reexports = paths.clone();
+            }
+        }
+        for finding in &findings {
+            on_finding(finding)?;
+        }
+        results.lock().unwrap().extend(findings);
+        Ok(())
+    })?;
+    Ok(results.into_inner().unwrap())
+}
+
+// 按被导出项所在的 crate 和名字索引的 `pub use` 导出路径。use 路径的第一段是扫描到的
+// crate 时项在那个 crate 中，否则（`crate::`、`self::`、`super::` 或子模块）在同一 crate 中
+fn re_export_index<'a>(parsed: &[&'a ParsedFile]) -> HashMap<(&'a str, &'a str), Vec<String>> {
+    let crate_of = |parsed_file: &'a ParsedFile| parsed_file.module_path.split("::").next().unwrap_or_default();
+    let crate_names: HashSet<&str> = parsed.iter().map(|parsed_file| crate_of(parsed_file)).collect();
+    let mut index: HashMap<(&str, &str), Vec<String>> = HashMap::new();
+    for parsed_file in parsed {
+        for re_export in &parsed_file.re_exports {
+            let source_crate = re_export
+                .source
+                .first()
+                .and_then(|first| crate_names.get(first.as_str()).copied())
+                .unwrap_or_else(|| crate_of(parsed_file));
+            let path = format!("{}::{}", parsed_file.module_path, re_export.path);
+            index.entry((source_crate, re_export.name.as_str())).or_default().push(path);
+        }
+    }
+    for paths in index.values_mut() {
+        paths.sort();
+        paths.dedup();
+    }
+    index
+}
+
+// 调用了 finding 中的函数的函数。写明了类型或者是自由函数的调用只匹配同一类型的函数，
+// 通过 self 的调用还可能是 trait 中的方法，其他方法调用不知道接收者的类型，匹配所有同名函数
+fn callers_of(finding: &Finding, call_sites: &[(&ParsedFile, &CallSite)]) -> Vec<Caller> {
+    let mut callers: Vec<Caller> = call_sites
+        .iter()
+        .filter(|(_, call_site)| match (&call_site.callee.owner, call_site.callee.explicit) {
+            (owner, true) => *owner == finding.owner,
+            (Some(owner), false) => finding.owner.as_ref() == Some(owner) || finding.kind == FindingKind::TraitMethod,
+            (None, false) => true,
+        })
+        .map(|(parsed_file, call_site)| Caller {
+            file: parsed_file.file.path.clone(),
+            line: call_site.line,
+            function: format!("{}::{}", parsed_file.module_path, call_site.caller),
+        })
+        .collect();
+    callers.sort();
+    callers.dedup();
+    callers
+}
+
+// 所有文件中到达名字匹配的函数的安全 pub 函数及其调用链，按文件分组
+fn safe_callers<'a>(settings: &Settings, parsed: &[&'a ParsedFile]) -> HashMap<&'a str, Vec<UncheckedFn>> {
+    let functions: Vec<(&ParsedFile, &CallerFn)> = parsed
+        .iter()
+        .flat_map(|parsed_file| parsed_file.call_nodes.iter().map(move |caller| (*parsed_file, caller)))
+        .collect();
+    let nodes: Vec<CallNode> = functions
+        .iter()
+        .map(|(parsed_file, caller)| CallNode {
+            crate_name: parsed_file.module_path.split("::").next().unwrap_or_default(),
+            owner: caller.function.owner.as_deref(),
+            name: &caller.function.name,
+            calls: &caller.calls,
+        })
+        .collect();
+    let chains = propagation::shortest_chains(&nodes, |callee| settings.pattern.is_match(callee));
+
+    // 调用链中的函数写成完整路径，例如 `alloc::vec::Vec::push`
+    let qualified_name = |index: usize| {
+        let (parsed_file, caller) = functions[index];
+        let function = &caller.function;
+        let mut path = vec![parsed_file.module_path.as_str()];
+        path.extend((!function.inline_module.is_empty()).then_some(function.inline_module.as_str()));
+        path.extend(function.owner.as_deref());
+        path.push(&function.name);
+        path.join("::")
+    };
+    let mut results: HashMap<&str, Vec<UncheckedFn>> = HashMap::new();
+    for (index, chain) in chains.into_iter().enumerate() {
+        let (parsed_file, caller) = functions[index];
+        let Some(chain) = chain.filter(|_| caller.reported) else {
+            continue;
+        };
+        let call_chain = std::iter::once(index)
+            .chain(chain.via)
+            .map(qualified_name)
+            .chain(std::iter::once(chain.callee))
+            .collect();
+        results
+            .entry(parsed_file.file.path.as_str())
+            .or_default()
+            .push(UncheckedFn { call_chain, ..caller.function.clone() });
+    }
+    results
+}
+
+// FunctionVisitor 找到的一个函数定义或调用
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct UncheckedFn {
+    name: String,
+    name_matches: bool,
+    // 文件中内联 mod 的嵌套路径，不在内联 mod 中时为空
+    inline_module: String,
+    owner: Option<String>,
+    // `impl Trait for Type` 中的方法实现的 trait
+    trait_name: Option<String>,
+    // 函数定义的完整签名，调用等其他结果没有
+    signature: Option<String>,
+    cfg: Vec<String>,
+    deprecated: bool,
+    stability: Option<Stability>,
+    const_fn: bool,
+    async_fn: bool,
+    // 在 #[test] 或 #[bench] 函数中，或者本身就是这样的函数
+    in_test_fn: bool,
+    // 只有 unsafe 函数才有
+    safety_doc: Option<bool>,
+    // `# Safety` 一节的内容，有这一节且不为空时才有
+    safety_section: Option<String>,
+    // 只有带函数体的定义才有
+    debug_checked: Option<bool>,
+    // 用于和安全版本比较签名
+    shape: Option<SignatureShape>,
+    caller: Option<String>,
+    kind: FindingKind,
+    span: Span,
+    count: Option<usize>,
+    // 从安全函数到名字匹配的函数的调用链，只有 SafeCaller 有
+    call_chain: Vec<String>,
+    // 只有 DocExample 有
+    example_calls: Vec<String>,
+}
+
+// 调用图中的一个函数定义
+struct CallerFn {
+    // 报告时使用的结果，种类为 SafeCaller
+    function: UncheckedFn,
+    // 是否是名字不匹配的安全 pub 函数，只有这样的函数会被报告，其他的只作为调用链的中间环节
+    reported: bool,
+    calls: HashSet<Callee>,
+}
+
+// `pub use` 重新导出的名字匹配的项
+struct ReExport {
+    // 被导出的项原来的名字
+    name: String,
+    // use 中项之前的路径，例如 `core::str`
+    source: Vec<String>,
+    // 导出后在文件中的路径，带有内联 mod，使用 `as` 改名时是新的名字
+    path: String,
+}
+
+// 函数中的一次调用
+struct CallSite {
+    callee: Callee,
+    // 调用所在的函数，带有文件中内联 mod 的路径
+    caller: String,
+    line: usize,
+}
+
+#[derive(Default)]
+struct FunctionCounts {
+    // unsafe 代码中的解引用
+    derefs: usize,
+    unsafe_blocks: usize,
+    // 对 MaybeUninit::assume_init 等的调用
+    assume_inits: usize,
+    // 调用的函数，只在检测 propagation 时收集
+    calls: HashSet<Callee>,
+}
+
+// 进入 mod、impl 等项之前的状态，离开时恢复
+struct OuterScope {
+    cfgs: usize,
+    stability: Option<Stability>,
+    in_test: bool,
+}
+
+struct FunctionVisitor {
+    unchecked_functions: HashSet<UncheckedFn>,
+    call_nodes: Vec<CallerFn>,
+    call_sites: Vec<CallSite>,
+    collect_call_sites: bool,
+    re_exports: Vec<ReExport>,
+    // 当前所在的内联 mod，最内层在最后
+    modules: Vec<String>,
+    // 文件和外层的 mod、impl、trait、extern 块和函数的 cfg 属性，最内层在最后
+    cfgs: Vec<String>,
+    // 最内层带有 #[stable] 或 #[unstable] 的外层项的稳定性
+    stability: Option<Stability>,
+    // 是否在 #[test] 或 #[bench] 函数中
+    in_test: bool,
+    scan_macros: bool,
+    // 当前所在 impl 的类型或 trait 名
+    impl_type: Option<String>,
+    // 查找调用时当前所在的函数，嵌套时最内层在最后
+    callers: Vec<String>,
+    // 与 callers 对应，每个函数中统计到的次数
+    counts: Vec<FunctionCounts>,
+    // 是否在 unsafe 块或 unsafe fn 中
+    in_unsafe: bool,
+    // 文件中定义的 union 的字段名
+    union_fields: HashSet<String>,
+    // 文件中用 use 导入的名字和导入的完整路径
+    imports: HashMap<String, Vec<String>>,
+    visibility: VisibilityFilter,
+    // 没有写可见性的项从外层继承的可见性：trait 中的方法同 trait，trait 的 impl 中的方法视为 pub
+    inherited_vis: Option<syn::Visibility>,
+    current_file: String,
+    pattern: Regex,
+    detect: Vec<Detect>,
+    // 判断函数定义和调用是否需要报告的规则
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl FunctionVisitor {
+    fn detects(&self, rule: Detect) -> bool {
+        self.detect.contains(&rule)
+    }
+
+    // 报告这个函数定义时的种类，不报告时为 None；第一个接受它的规则决定种类
+    fn target_kind(&self, name: &str, is_unsafe: bool, kind: FindingKind) -> Option<FindingKind> {
+        self.rules.iter().find(|rule| rule.check_fn(name, is_unsafe)).map(|rule| rule.fn_kind(kind))
+    }
+
+    // 第一个接受这个调用的规则决定它的种类
+    fn check_call(&mut self, callee: &Ident, path: Option<&[String]>) {
+        // `use .. as 别名` 导入的函数按原来的名字检查和报告
+        let name = path.and_then(<[String]>::last).cloned().unwrap_or_else(|| callee.to_string());
+        if let (true, Some(counts)) = (ASSUME_INIT.contains(&name.as_str()), self.counts.last_mut()) {
+            counts.assume_inits += 1;
+        }
+        if let Some(kind) = self.rules.iter().find_map(|rule| rule.check_call(&name, path)) {
+            self.record_call(callee, name, kind);
+        }
+    }
+
+    // 项的 cfg 属性和稳定性对其中的所有项生效
+    fn enter(&mut self, attrs: &[Attribute]) -> OuterScope {
+        let outer = OuterScope { cfgs: self.cfgs.len(), stability: self.stability.clone(), in_test: self.in_test };
+        self.cfgs.extend(cfg_attributes(attrs));
+        if let Some(stability) = stability(attrs) {
+            self.stability = Some(stability);
+        }
+        self.in_test |= is_test_fn(attrs);
+        outer
+    }
+
+    fn leave(&mut self, outer: OuterScope) {
+        self.cfgs.truncate(outer.cfgs);
+        self.stability = outer.stability;
+        self.in_test = outer.in_test;
+    }
+
+    fn effective_vis<'v>(&'v self, vis: &'v syn::Visibility) -> &'v syn::Visibility {
+        match (vis, &self.inherited_vis) {
+            (syn::Visibility::Inherited, Some(inherited)) => inherited,
+            _ => vis,
+        }
+    }
+
+    // 记录调用图中的一条边，`owner` 是写明的类型或 self 所属的类型
+    fn add_callee(&mut self, callee: &Ident, owner: Option<String>, explicit: bool) {
+        let callee_fn = Callee { name: callee.to_string(), owner, explicit };
+        if let (true, Some(caller)) = (self.collect_call_sites, self.callers.last()) {
+            let caller = self.modules.iter().chain(std::iter::once(caller)).cloned().collect::<Vec<_>>().join("::");
+            self.call_sites.push(CallSite { callee: callee_fn.clone(), caller, line: callee.span().start().line });
+        }
+        if let (true, Some(counts)) = (self.detects(Detect::Propagation), self.counts.last_mut()) {
+            counts.calls.insert(callee_fn);
+        }
+    }
+
+    fn visible(&self, vis: &syn::Visibility) -> bool {
+        self.visibility.accepts(self.effective_vis(vis))
+    }
+
+    // 导出的符号可以被其他语言直接调用，单独报告，不管可见性
+    fn check_exported(&mut self, attrs: &[Attribute], sig: &Signature, body: &Block, owner: Option<String>) {
+        if self.detects(Detect::Exported)
+            && is_exported(attrs)
+            && (sig.unsafety.is_some() || self.pattern.is_match(&sig.ident.to_string()))
+        {
+            log::trace!("{}: found exported `{}`", self.current_file, sig.ident);
+            self.record_fn(attrs, sig, Some(body), owner, FindingKind::Exported);
+        }
+    }
+
+    // 文档中没有写明前提的示例，每个示例一个结果，位置是示例开始的行
+    fn check_doc_examples(&mut self, attrs: &[Attribute], ident: &Ident, owner: Option<String>) {
+        if !self.detects(Detect::DocExamples) {
+            return;
+        }
+        for example in doctest::examples(attrs, &self.pattern) {
+            if example.shows_preconditions {
+                continue;
+            }
+            log::trace!("{}: found doc example of `{}` at line {}", self.current_file, ident, example.line);
+            let mut unchecked_fn = self.unchecked_fn(ident, owner.clone(), FindingKind::DocExample);
+            unchecked_fn.cfg.extend(cfg_attributes(attrs));
+            unchecked_fn.span = Span { line: example.line, column: 1 };
+            unchecked_fn.count = (example.unsafe_blocks > 0).then_some(example.unsafe_blocks);
+            unchecked_fn.example_calls = example.unchecked_calls;
+            self.unchecked_functions.insert(unchecked_fn);
+        }
+    }
+
+    fn with_caller(&mut self, attrs: &[Attribute], vis: &syn::Visibility, sig: &Signature, visit: impl FnOnce(&mut Self)) {
+        let name = &sig.ident;
+        let visible = self.visible(vis);
+        if visible {
+            self.check_doc_examples(attrs, name, self.impl_type.clone());
+        }
+        let public = VisibilityFilter::Pub.accepts(self.effective_vis(vis));
+        let caller = match &self.impl_type {
+            Some(impl_type) => format!("{impl_type}::{name}"),
+            None => name.to_string(),
+        };
+        self.callers.push(caller);
+        self.counts.push(FunctionCounts::default());
+        // 外层的 unsafe 块不会延续到嵌套的函数中
+        let outer_unsafe = std::mem::replace(&mut self.in_unsafe, sig.unsafety.is_some());
+        // 函数体中的项不继承 trait 的可见性
+        let outer_vis = self.inherited_vis.take();
+        let outer_scope = self.enter(attrs);
+        visit(self);
+        self.leave(outer_scope);
+        self.inherited_vis = outer_vis;
+        self.in_unsafe = outer_unsafe;
+        self.callers.pop();
+
+        let counts = self.counts.pop().unwrap_or_default();
+        if self.detects(Detect::Propagation) {
+            self.call_nodes.push(CallerFn {
+                function: self.function(attrs, sig, None, self.impl_type.clone(), FindingKind::SafeCaller),
+                reported: visible && public && sig.unsafety.is_none() && !self.pattern.is_match(&name.to_string()),
+                calls: counts.calls,
+            });
+        }
+        if !visible {
+            return;
+        }
+        if self.detects(Detect::PtrDeref) && counts.derefs > 0 {
+            log::trace!("{}: found {} dereferences in `{}`", self.current_file, counts.derefs, name);
+            self.record_count(attrs, sig, FindingKind::PtrDeref, counts.derefs);
+        }
+        if self.detects(Detect::UnsafeMetrics) && counts.unsafe_blocks > 0 {
+            log::trace!("{}: found {} unsafe blocks in `{}`", self.current_file, counts.unsafe_blocks, name);
+            self.record_count(attrs, sig, FindingKind::UnsafeBlocks, counts.unsafe_blocks);
+        }
+        if self.detects(Detect::SafeWrappers) && public && sig.unsafety.is_none() && counts.unsafe_blocks > 0 {
+            log::trace!("{}: found safe `{}` with {} unsafe blocks", self.current_file, name, counts.unsafe_blocks);
+            self.record_count(attrs, sig, FindingKind::SafeWrapper, counts.unsafe_blocks);
+        }
+        if self.detects(Detect::AssumeInit) && counts.assume_inits > 0 {
+            log::trace!("{}: found {} assume_init calls in `{}`", self.current_file, counts.assume_inits, name);
+            self.record_count(attrs, sig, FindingKind::AssumeInit, counts.assume_inits);
+        }
+    }
+
+    fn record_count(&mut self, attrs: &[Attribute], sig: &Signature, kind: FindingKind, count: usize) {
+        let function = UncheckedFn {
+            count: Some(count),
+            ..self.function(attrs, sig, None, self.impl_type.clone(), kind)
+        };
+        self.unchecked_functions.insert(function);
+    }
+
+    fn record_call(&mut self, callee: &Ident, name: String, kind: FindingKind) {
+        log::trace!("{}: found call to `{}`", self.current_file, name);
+        let call = UncheckedFn {
+            name_matches: self.pattern.is_match(&name),
+            name,
+            // 不在函数中的调用（例如 static 的初始化）没有调用者
+            caller: self.callers.last().cloned(),
+            ..self.unchecked_fn(callee, None, kind)
+        };
+        self.unchecked_functions.insert(call);
+    }
+
+    fn record(&mut self, attrs: &[Attribute], ident: &Ident, owner: Option<String>, kind: FindingKind) {
+        let mut unchecked_fn = self.unchecked_fn(ident, owner, kind);
+        unchecked_fn.cfg.extend(cfg_attributes(attrs));
+        unchecked_fn.deprecated = is_deprecated(attrs);
+        unchecked_fn.stability = stability(attrs).or(unchecked_fn.stability);
+        self.unchecked_functions.insert(unchecked_fn);
+    }
+
+    fn record_fn(&mut self, attrs: &[Attribute], sig: &Signature, body: Option<&Block>, owner: Option<String>, kind: FindingKind) {
+        let function = self.function(attrs, sig, body, owner, kind);
+        self.unchecked_functions.insert(function);
+    }
+
+    // 带签名的函数定义，unsafe 函数还要记录文档中有没有 `# Safety` 一节，
+    // 有函数体的记录其中有没有 debug_assert! 检查前提条件
+    fn function(
+        &self,
+        attrs: &[Attribute],
+        sig: &Signature,
+        body: Option<&Block>,
+        owner: Option<String>,
+        kind: FindingKind,
+    ) -> UncheckedFn {
+        let unchecked_fn = self.unchecked_fn(&sig.ident, owner, kind);
+        let section = safety_section(attrs);
+        UncheckedFn {
+            signature: Some(render_signature(sig)),
+            cfg: unchecked_fn.cfg.iter().cloned().chain(cfg_attributes(attrs)).collect(),
+            deprecated: is_deprecated(attrs),
+            stability: stability(attrs).or(unchecked_fn.stability.clone()),
+            const_fn: sig.constness.is_some(),
+            async_fn: sig.asyncness.is_some(),
+            in_test_fn: unchecked_fn.in_test_fn || is_test_fn(attrs),
+            safety_doc: sig.unsafety.is_some().then(|| section.is_some()),
+            safety_section: section.filter(|text| !text.is_empty()),
+            debug_checked: body.map(has_debug_assert),
+            shape: Some(SignatureShape::new(sig, sig.unsafety.is_some() || kind == FindingKind::Ffi)),
+            ..unchecked_fn
+        }
+    }
+
+    fn unchecked_fn(&self, ident: &Ident, owner: Option<String>, kind: FindingKind) -> UncheckedFn {
+        let start = ident.span().start();
+        UncheckedFn {
+            name: ident.to_string(),
+            name_matches: self.pattern.is_match(&ident.to_string()),
+            inline_module: self.modules.join("::"),
+            owner,
+            trait_name: None,
+            signature: None,
+            cfg: self.cfgs.clone(),
+            deprecated: false,
+            stability: self.stability.clone(),
+            const_fn: false,
+            async_fn: false,
+            in_test_fn: self.in_test,
+            safety_doc: None,
+            safety_section: None,
+            debug_checked: None,
+            shape: None,
+            caller: None,
+            kind,
+            // proc_macro2 的列号从 0 开始
+            span: Span { line: start.line, column: start.column + 1 },
+            count: None,
+            call_chain: Vec::new(),
+            example_calls: Vec::new(),
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for FunctionVisitor {
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        let fn_name = node.sig.ident.to_string();
+
+        let kind = self.target_kind(&fn_name, node.sig.unsafety.is_some(), FindingKind::Function);
+        if let (true, Some(kind)) = (self.visible(&node.vis), kind) {
+            log::trace!("{}: found `{}`", self.current_file, fn_name);
+            self.record_fn(&node.attrs, &node.sig, Some(&node.block), None, kind);
+        }
+        self.check_exported(&node.attrs, &node.sig, &node.block, None);
+
+        // 遍历函数的其他部分
+        self.with_caller(&node.attrs, &node.vis, &node.sig, |visitor| visit::visit_item_fn(visitor, node));
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.with_caller(&node.attrs, &node.vis, &node.sig, |visitor| visit::visit_impl_item_fn(visitor, node));
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast TraitItemFn) {
+        self.with_caller(&node.attrs, &syn::Visibility::Inherited, &node.sig, |visitor| {
+            visit::visit_trait_item_fn(visitor, node)
+        });
+    }
+
+    fn visit_expr_unsafe(&mut self, node: &'ast ExprUnsafe) {
+        if let Some(counts) = self.counts.last_mut() {
+            counts.unsafe_blocks += 1;
+        }
+        let outer_unsafe = std::mem::replace(&mut self.in_unsafe, true);
+        visit::visit_expr_unsafe(self, node);
+        self.in_unsafe = outer_unsafe;
+    }
+
+    fn visit_expr_unary(&mut self, node: &'ast ExprUnary) {
+        // 没有类型信息，分不清解引用的是裸指针还是引用，只能认为 unsafe 代码中的解引用都可能是裸指针。
+        // 安全代码中不能解引用裸指针，所以 unsafe 之外的解引用不计
+        if let (UnOp::Deref(_), true, Some(counts)) = (node.op, self.in_unsafe, self.counts.last_mut()) {
+            counts.derefs += 1;
+        }
+        visit::visit_expr_unary(self, node);
+    }
+
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        // 例如 `str::from_utf8_unchecked(bytes)`
+        if let Expr::Path(path) = &*node.func {
+            let segments: Vec<&Ident> = path.path.segments.iter().map(|segment| &segment.ident).collect();
+            if let Some(&callee) = segments.last() {
+                // 用 use 导入的名字换成导入的完整路径
+                let path: Vec<String> = match self.imports.get(&segments[0].to_string()) {
+                    Some(imported) => imported.iter().cloned().chain(segments[1..].iter().map(|segment| segment.to_string())).collect(),
+                    None => segments.iter().map(|segment| segment.to_string()).collect(),
+                };
+                self.check_call(callee, Some(&path));
+                // `Self::f` 是当前类型的函数，`Type::f` 是写明的类型的，小写开头的是模块中的函数
+                match segments.iter().rev().nth(1).map(|qualifier| qualifier.to_string()) {
+                    Some(qualifier) if qualifier == "Self" => self.add_callee(callee, self.impl_type.clone(), false),
+                    Some(qualifier) if qualifier.starts_with(char::is_uppercase) => self.add_callee(callee, Some(qualifier), true),
+                    _ => self.add_callee(callee, None, true),
+                }
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        // 例如 `slice.get_unchecked(i)`
+        self.check_call(&node.method, None);
+        let on_self = matches!(&*node.receiver, Expr::Path(path) if path.path.is_ident("self"));
+        self.add_callee(&node.method, self.impl_type.clone().filter(|_| on_self), false);
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        // 宏参数中的调用只按 token 查找，只用于调用链
+        if let (true, Some(counts)) = (self.detects(Detect::Propagation), self.counts.last_mut()) {
+            counts.calls.extend(token_calls(node.tokens.clone()).into_iter().map(Callee::named));
+        }
+        visit::visit_macro(self, node);
+    }
+
+    fn visit_expr_field(&mut self, node: &'ast ExprField) {
+        // 没有类型信息，只能按字段名判断；读取 union 字段必须在 unsafe 中，所以 unsafe 之外的同名字段不会误报
+        if let (true, true, Member::Named(field)) = (self.detects(Detect::Unions), self.in_unsafe, &node.member) {
+            if self.union_fields.contains(&field.to_string()) {
+                self.record_call(field, field.to_string(), FindingKind::UnionAccess);
+            }
+        }
+        visit::visit_expr_field(self, node);
+    }
+
+    fn visit_expr_assign(&mut self, node: &'ast ExprAssign) {
+        // 给 union 字段赋值是安全的，不算读取
+        match &*node.left {
+            Expr::Field(field) => {
+                self.visit_expr(&field.base);
+                self.visit_expr(&node.right);
+            }
+            _ => visit::visit_expr_assign(self, node),
+        }
+    }
+
+    fn visit_item_use(&mut self, node: &'ast ItemUse) {
+        if matches!(node.vis, syn::Visibility::Public(_)) {
+            let mut leaves = Vec::new();
+            use_leaves(&node.tree, &mut Vec::new(), &mut leaves);
+            for (source, name, alias) in leaves {
+                if self.pattern.is_match(&name) {
+                    log::trace!("{}: found re-export of `{}`", self.current_file, name);
+                    let path = self.modules.iter().chain(std::iter::once(&alias)).cloned().collect::<Vec<_>>().join("::");
+                    self.re_exports.push(ReExport { name, source, path });
+                }
+            }
+        }
+        visit::visit_item_use(self, node);
+    }
+
+    fn visit_item_static(&mut self, node: &'ast ItemStatic) {
+        if let (true, StaticMutability::Mut(_)) = (self.detects(Detect::StaticMut) && self.visible(&node.vis), &node.mutability) {
+            log::trace!("{}: found `static mut {}`", self.current_file, node.ident);
+            self.record(&node.attrs, &node.ident, None, FindingKind::StaticMut);
+        }
+        visit::visit_item_static(self, node);
+    }
+
+    fn visit_item_union(&mut self, node: &'ast ItemUnion) {
+        if self.detects(Detect::Unions) && self.visible(&node.vis) {
+            log::trace!("{}: found union `{}`", self.current_file, node.ident);
+            self.record(&node.attrs, &node.ident, None, FindingKind::Union);
+        }
+        visit::visit_item_union(self, node);
+    }
+
+    fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
+        if self.visible(&node.vis) {
+            self.check_doc_examples(&node.attrs, &node.ident, None);
+        }
+        visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+        if self.visible(&node.vis) {
+            self.check_doc_examples(&node.attrs, &node.ident, None);
+        }
+        visit::visit_item_enum(self, node);
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast ItemMod) {
+        if self.visible(&node.vis) {
+            self.check_doc_examples(&node.attrs, &node.ident, None);
+        }
+        self.modules.push(node.ident.to_string());
+        let outer_scope = self.enter(&node.attrs);
+        visit::visit_item_mod(self, node);
+        self.leave(outer_scope);
+        self.modules.pop();
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        let type_name = type_name(&node.self_ty);
+        let implemented_trait = node.trait_.as_ref().map(|(_, trait_path, _)| path_name(trait_path));
+        let public = node.trait_.is_some().then(|| syn::Visibility::Public(Default::default()));
+        let outer_vis = std::mem::replace(&mut self.inherited_vis, public);
+        let outer_scope = self.enter(&node.attrs);
+        if let (true, Some(_), Some((_, trait_path, _))) = (self.detects(Detect::UnsafeImpl), node.unsafety, &node.trait_) {
+            if let Some(segment) = trait_path.segments.last() {
+                log::trace!("{}: found `unsafe impl {} for {}`", self.current_file, segment.ident, type_name);
+                self.record(&[], &segment.ident, Some(type_name.clone()), FindingKind::UnsafeImpl);
+            }
+        }
+        // 遍历 impl 中的所有函数
+        for item in &node.items {
+            if let ImplItem::Fn(item_fn) = item {
+                let method_name = item_fn.sig.ident.to_string();
+
+                let kind = self.target_kind(&method_name, item_fn.sig.unsafety.is_some(), FindingKind::Method);
+                if let (true, Some(kind)) = (self.visible(&item_fn.vis), kind) {
+                    log::trace!("{}: found method `{}::{}`", self.current_file, type_name, method_name);
+                    let method = UncheckedFn {
+                        trait_name: implemented_trait.clone(),
+                        ..self.function(&item_fn.attrs, &item_fn.sig, Some(&item_fn.block), Some(type_name.clone()), kind)
+                    };
+                    self.unchecked_functions.insert(method);
+                }
+                self.check_exported(&item_fn.attrs, &item_fn.sig, &item_fn.block, Some(type_name.clone()));
+            }
+        }
+        // 继续遍历 impl 结构的其他部分
+        let outer = self.impl_type.replace(type_name);
+        visit::visit_item_impl(self, node);
+        self.impl_type = outer;
+        self.leave(outer_scope);
+        self.inherited_vis = outer_vis;
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        if self.visible(&node.vis) {
+            self.check_doc_examples(&node.attrs, &node.ident, None);
+        }
+        // trait 中的方法声明和默认实现
+        let outer_vis = self.inherited_vis.replace(node.vis.clone());
+        let outer_scope = self.enter(&node.attrs);
+        for item in &node.items {
+            if let TraitItem::Fn(trait_fn) = item {
+                let method_name = trait_fn.sig.ident.to_string();
+
+                let kind = self.target_kind(&method_name, trait_fn.sig.unsafety.is_some(), FindingKind::TraitMethod);
+                if let (true, Some(kind)) = (self.visible(&syn::Visibility::Inherited), kind) {
+                    log::trace!("{}: found trait method `{}::{}`", self.current_file, node.ident, method_name);
+                    let owner = Some(node.ident.to_string());
+                    self.record_fn(&trait_fn.attrs, &trait_fn.sig, trait_fn.default.as_ref(), owner, kind);
+                }
+            }
+        }
+        let outer = self.impl_type.replace(node.ident.to_string());
+        visit::visit_item_trait(self, node);
+        self.impl_type = outer;
+        self.leave(outer_scope);
+        self.inherited_vis = outer_vis;
+    }
+
+    fn visit_item_foreign_mod(&mut self, node: &'ast ItemForeignMod) {
+        let outer_scope = self.enter(&node.attrs);
+        for item in &node.items {
+            if let ForeignItem::Fn(foreign_fn) = item {
+                let fn_name = foreign_fn.sig.ident.to_string();
+
+                // 外部函数总是 unsafe 的
+                let kind = self.target_kind(&fn_name, true, FindingKind::Ffi);
+                if let (true, Some(kind)) = (self.visible(&foreign_fn.vis), kind) {
+                    log::trace!("{}: found foreign function `{}`", self.current_file, fn_name);
+                    self.record_fn(&foreign_fn.attrs, &foreign_fn.sig, None, None, kind);
+                }
+            }
+        }
+        visit::visit_item_foreign_mod(self, node);
+        self.leave(outer_scope);
+    }
+
+    fn visit_item_macro(&mut self, node: &'ast ItemMacro) {
+        // 宏展开前看不到生成的函数，只能在 macro_rules! 的定义中按 token 查找；
+        // 扫描展开后的代码时已经能找到真正生成的函数了
+        if let (true, Some(macro_name)) = (self.scan_macros && node.mac.path.is_ident("macro_rules"), &node.ident) {
+            for MacroFn { ident, is_unsafe, .. } in macro_functions(node.mac.tokens.clone()) {
+                if let Some(kind) = self.target_kind(&ident.to_string(), is_unsafe, FindingKind::MacroGenerated) {
+                    log::trace!("{}: found `{}` in macro `{}`", self.current_file, ident, macro_name);
+                    self.record(&node.attrs, &ident, Some(format!("{macro_name}!")), kind);
+                }
+            }
+        }
+        visit::visit_item_macro(self, node);
+    }
+}
+
+// 宏定义中按 token 找到的一个函数
+struct MacroFn {
+    ident: Ident,
+    is_unsafe: bool,
+    // 函数体中调用的函数；函数体是元变量（例如 `$body`）或没有函数体时为 None
+    calls: Option<HashSet<String>>,
+}
+
+// use 树中导入的每一项的路径、名字和导入后的名字，`*` 不算
+fn use_leaves(tree: &UseTree, path: &mut Vec<String>, leaves: &mut Vec<(Vec<String>, String, String)>) {
+    match tree {
+        UseTree::Path(use_path) => {
+            path.push(use_path.ident.to_string());
+            use_leaves(&use_path.tree, path, leaves);
+            path.pop();
+        }
+        UseTree::Name(name) => leaves.push((path.clone(), name.ident.to_string(), name.ident.to_string())),
+        UseTree::Rename(rename) => leaves.push((path.clone(), rename.ident.to_string(), rename.rename.to_string())),
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                use_leaves(tree, path, leaves);
+            }
+        }
+        UseTree::Glob(_) => {}
+    }
+}
+
+// 在宏定义的 token 中查找 `fn 名字`，同时返回它前面是否有 unsafe。
+// 名字由元变量拼出来的函数（例如 paste!）找不到
+fn macro_functions(tokens: TokenStream) -> Vec<MacroFn> {
+    let mut functions: Vec<MacroFn> = Vec::new();
+    let mut is_unsafe = false;
+    let mut after_fn = false;
+    // 还没有遇到函数体的上一个函数
+    let mut without_body = None;
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) if after_fn => {
+                without_body = Some(functions.len());
+                functions.push(MacroFn { ident, is_unsafe, calls: None });
+                after_fn = false;
+                is_unsafe = false;
+            }
+            TokenTree::Ident(ident) if ident == "fn" => {
+                after_fn = true;
+                without_body = None;
+            }
+            TokenTree::Ident(ident) if ident == "unsafe" => is_unsafe = true,
+            // fn 前面可能出现的其他限定词，例如 `pub(crate) const unsafe extern "C" fn`
+            TokenTree::Ident(ident) if ["pub", "const", "async", "extern", "default"].iter().any(|word| ident == word) => {}
+            TokenTree::Literal(_) => {}
+            // 宏的匹配分支和重复部分都在分组中
+            TokenTree::Group(group) => {
+                // 函数名之后的第一个花括号分组是函数体
+                if group.delimiter() == Delimiter::Brace {
+                    if let Some(index) = without_body.take() {
+                        functions[index].calls = Some(token_calls(group.stream()));
+                    }
+                }
+                functions.extend(macro_functions(group.stream()));
+                after_fn = false;
+                is_unsafe = false;
+            }
+            TokenTree::Punct(punct) if punct.as_char() == ';' => {
+                without_body = None;
+                after_fn = false;
+                is_unsafe = false;
+            }
+            _ => {
+                after_fn = false;
+                is_unsafe = false;
+            }
+        }
+    }
+    functions
+}
+
+// token 中所有后面紧跟圆括号分组的名字，即没有解析的代码中调用的函数和方法
+fn token_calls(tokens: TokenStream) -> HashSet<String> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut calls = HashSet::new();
+    for (index, token) in tokens.iter().enumerate() {
+        let TokenTree::Group(group) = token else {
+            continue;
+        };
+        if let (Delimiter::Parenthesis, Some(TokenTree::Ident(ident))) = (group.delimiter(), index.checked_sub(1).map(|previous| &tokens[previous])) {
+            // `fn 名字(` 中的名字是定义而不是调用
+            let is_definition = matches!(index.checked_sub(2).map(|fn_index| &tokens[fn_index]), Some(TokenTree::Ident(keyword)) if keyword == "fn");
+            if !is_definition {
+                calls.insert(ident.to_string());
+            }
+        }
+        calls.extend(token_calls(group.stream()));
+    }
+    calls
+}
+
+// 文档注释中标题为 Safety 的一节的内容
+fn safety_section(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue { value: Expr::Lit(lit), .. }) => match &lit.lit {
+                syn::Lit::Str(doc) => Some(doc.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|doc| doc.lines().map(|line| line.trim().to_string()).collect::<Vec<_>>())
+        .collect();
+    safety_section_in(&lines)
+}
+
+/// The `# Safety` section of the documentation `docs`, like the one
+/// reported for doc comments.
+pub fn doc_safety_section(docs: &str) -> Option<String> {
+    let lines: Vec<String> = docs.lines().map(|line| line.trim().to_string()).collect();
+    safety_section_in(&lines)
+}
+
+// 标题为 Safety 的一节，和 clippy 的 missing_safety_doc 一样不管标题级别；
+// 段落之间空一行，段落中的行连成一行，代码块保持原样。没有这一节时为 None
+fn safety_section_in(lines: &[String]) -> Option<String> {
+    let is_heading = |line: &str| line.starts_with('#') && line.trim_start_matches('#').starts_with(' ');
+    let start = lines
+        .iter()
+        .position(|line| is_heading(line) && line.trim_start_matches('#').trim().eq_ignore_ascii_case("safety"))?;
+
+    let mut paragraphs = Vec::new();
+    let mut paragraph = String::new();
+    let mut in_code = false;
+    for line in &lines[start + 1..] {
+        if !in_code && is_heading(line) {
+            break;
+        }
+        if !in_code && line.is_empty() {
+            if !paragraph.is_empty() {
+                paragraphs.push(std::mem::take(&mut paragraph));
+            }
+            continue;
+        }
+        // 链接的定义，例如 `[`downcast`]: Self::downcast`，不显示在文档中
+        if !in_code && line.starts_with('[') && line.contains("]: ") {
+            continue;
+        }
+        let is_fence = line.starts_with("```");
+        if !paragraph.is_empty() {
+            paragraph.push(if in_code || is_fence { '\n' } else { ' ' });
+        }
+        paragraph.push_str(line);
+        in_code ^= is_fence;
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+    Some(paragraphs.join("\n\n"))
+}
+
+// 函数体中有没有 debug_assert!、debug_assert_eq! 等或 std 的 assert_unsafe_precondition!，
+// 只能找到直接写在函数体中的宏，其他宏展开出来的找不到
+fn has_debug_assert(body: &Block) -> bool {
+    #[derive(Default)]
+    struct DebugAssertVisitor {
+        found: bool,
+    }
+
+    impl<'ast> Visit<'ast> for DebugAssertVisitor {
+        fn visit_macro(&mut self, node: &'ast syn::Macro) {
+            if let Some(segment) = node.path.segments.last() {
+                let name = segment.ident.to_string();
+                self.found |= name.starts_with("debug_assert") || name == "assert_unsafe_precondition";
+            }
+            visit::visit_macro(self, node);
+        }
+    }
+
+    let mut visitor = DebugAssertVisitor::default();
+    visitor.visit_block(body);
+    visitor.found
+}
+
+// `#[no_mangle]` 或 `#[export_name = "..."]`
+fn is_exported(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("no_mangle") || attr.path().is_ident("export_name"))
+}
+
+// 先收集文件中所有 union 的字段名，之后才能认出对它们的读取
+#[derive(Default)]
+struct UnionVisitor {
+    fields: HashSet<String>,
+}
+
+impl<'ast> Visit<'ast> for UnionVisitor {
+    fn visit_item_union(&mut self, node: &'ast ItemUnion) {
+        self.fields.extend(node.fields.named.iter().filter_map(|field| field.ident.as_ref().map(Ident::to_string)));
+        visit::visit_item_union(self, node);
+    }
+}
+
+// 文件中所有 use 导入的名字，不区分所在的 mod；`*` 导入的名字无法知道
+#[derive(Default)]
+struct UseVisitor {
+    imports: HashMap<String, Vec<String>>,
+}
+
+impl<'ast> Visit<'ast> for UseVisitor {
+    fn visit_item_use(&mut self, node: &'ast ItemUse) {
+        let mut leaves = Vec::new();
+        use_leaves(&node.tree, &mut Vec::new(), &mut leaves);
+        for (mut path, name, alias) in leaves {
+            // `use core::intrinsics::{self}` 导入的是模块本身，名字是模块名
+            let alias = match (alias.as_str(), path.last()) {
+                ("self", Some(module)) => module.clone(),
+                ("self", None) => continue,
+                _ => alias,
+            };
+            if name != "self" {
+                path.push(name);
+            }
+            self.imports.insert(alias, path);
+        }
+    }
+}
+
+/// Short name of an impl's self type as written in the source, without
+/// generic arguments, e.g. `Vec`, `[T]` or `*const T`.
+pub fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.to_string()).unwrap_or_default(),
+        Type::Slice(slice) => format!("[{}]", type_name(&slice.elem)),
+        Type::Array(array) => format!("[{}; _]", type_name(&array.elem)),
+        Type::Ptr(ptr) => {
+            let mutability = if ptr.mutability.is_some() { "mut" } else { "const" };
+            format!("*{} {}", mutability, type_name(&ptr.elem))
+        }
+        Type::Reference(reference) => {
+            let mutability = if reference.mutability.is_some() { "mut " } else { "" };
+            format!("&{}{}", mutability, type_name(&reference.elem))
+        }
+        Type::Paren(paren) => type_name(&paren.elem),
+        Type::Group(group) => type_name(&group.elem),
+        // 其他少见的类型直接使用源码
+        ty => ty.to_token_stream().to_string(),
+    }
+}
+
+fn is_test_fn(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("test") || attr.path().is_ident("bench"))
+}
+
+// tests/ 或 benches/ 目录下的文件，以及 std 中习惯用 `#[cfg(test)] mod tests;` 引入的 tests.rs
+fn is_test_file(file: &SourceFile) -> bool {
+    let path = Path::new(&file.path);
+    let relative = path.strip_prefix(&file.root).unwrap_or(path);
+    relative.file_name().is_some_and(|name| name == "tests.rs")
+        || relative
+            .parent()
+            .is_some_and(|parent| parent.components().any(|component| matches!(component.as_os_str().to_str(), Some("tests" | "benches"))))
+}
+
+fn is_deprecated(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| attr.path().is_ident("deprecated"))
+}
+
+// 标准库中的 `#[stable(feature = "..", since = "..")]` 和 `#[unstable(feature = "..", issue = "..")]`
+fn stability(attrs: &[Attribute]) -> Option<Stability> {
+    attrs.iter().find_map(|attr| {
+        let level = if attr.path().is_ident("stable") {
+            StabilityLevel::Stable
+        } else if attr.path().is_ident("unstable") {
+            StabilityLevel::Unstable
+        } else {
+            return None;
+        };
+        let mut feature = None;
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("feature") {
+                feature = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            } else {
+                // 跳过 since、issue 等其他参数的值
+                let _ = meta.value().and_then(|value| value.parse::<syn::Lit>());
+            }
+            Ok(())
+        });
+        Some(Stability { level, feature: feature.unwrap_or_default() })
+    })
+}
+
+// 条件编译相关的属性，格式化成 `cfg(unix)` 的形式
+fn cfg_attributes(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg") || attr.path().is_ident("cfg_attr"))
+        .map(|attr| {
+            // 文件开头的内部属性也按外部属性格式化
+            let meta = &attr.meta;
+            let rendered = unparse_fn(syn::parse_quote!(#[#meta] fn f() {}));
+            let rendered = rendered.trim_end().trim_end_matches("fn f() {}").trim_end();
+            join_lines(rendered).trim_start_matches("#[").trim_end_matches(']').to_string()
+        })
+        .collect()
+}
+
+/// `sig` as written in source, formatted by prettyplease on one line.
+pub fn render_signature(sig: &Signature) -> String {
+    let rendered = unparse_fn(syn::parse_quote!(#sig {}));
+    join_lines(rendered.trim_end().trim_end_matches("{}").trim_end())
+}
+
+fn unparse_fn(item: syn::ItemFn) -> String {
+    let file = syn::File { shebang: None, attrs: Vec::new(), items: vec![syn::Item::Fn(item)] };
+    prettyplease::unparse(&file)
+}
+
+fn join_lines(rendered: &str) -> String {
+    rendered
+        .lines()
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace("( ", "(")
+        .replace(", )", ")")
+        .replace(" )", ")")
+        .replace("< ", "<")
+        .replace(", >", ">")
+        .trim_end_matches(',')
+        .to_string()
+}
+
+// trait 的名字连同泛型参数，例如 `SliceIndex<[T]>`，参数中的类型同样用 type_name 简写
+fn path_name(path: &syn::Path) -> String {
+    let Some(segment) = path.segments.last() else {
+        return String::new();
+    };
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return segment.ident.to_string();
+    };
+    let arguments: Vec<String> = arguments
+        .args
+        .iter()
+        .map(|argument| match argument {
+            GenericArgument::Type(ty) => type_name(ty),
+            argument => argument.to_token_stream().to_string(),
+        })
+        .collect();
+    format!("{}<{}>", segment.ident, arguments.join(", "))
+}
+
+fn process_file(file: &SourceFile, settings: &Settings) -> Result<ParsedFile> {
+    let file_content = file.read()?; // 读取文件内容
+    let parsed_file = parse_file(&file_content)?; // 解析 Rust 文件
+
+    let mut unions = UnionVisitor::default();
+    if settings.detect.contains(&Detect::Unions) {
+        unions.visit_file(&parsed_file);
+    }
+    let mut imports = UseVisitor::default();
+    if settings.detect.contains(&Detect::Intrinsics) {
+        imports.visit_file(&parsed_file);
+    }
+
+    // 创建一个函数访问者
+    let mut visitor = FunctionVisitor {
+        unchecked_functions: HashSet::new(),
+        call_nodes: Vec::new(),
+        call_sites: Vec::new(),
+        collect_call_sites: settings.callers,
+        re_exports: Vec::new(),
+        modules: Vec::new(),
+        // 文件开头的 `#![cfg(..)]` 对整个文件生效
+        cfgs: cfg_attributes(&parsed_file.attrs),
+        stability: stability(&parsed_file.attrs),
+        in_test: false,
+        scan_macros: !settings.expand,
+        impl_type: None,
+        callers: Vec::new(),
+        counts: Vec::new(),
+        in_unsafe: false,
+        union_fields: unions.fields,
+        imports: imports.imports,
+        visibility: settings.visibility,
+        inherited_vis: None,
+        detect: settings.detect.clone(),
+        rules: rules::rules(&settings.detect, &settings.pattern),
+        current_file: file.path.clone(), // 设置当前文件路径
+        pattern: settings.pattern.clone(),
+    };
+
+    // 遍历文件中的所有项
+    visitor.visit_file(&parsed_file);
+
+    let mut unchecked_functions: Vec<UncheckedFn> = visitor.unchecked_functions.into_iter().collect();
+    unchecked_functions.sort_by_key(|unchecked_fn| (unchecked_fn.span.line, unchecked_fn.span.column));
+    // 只保留函数定义的汇总，不需要保留语法树
+    let mut definitions = DefinitionVisitor::default();
+    definitions.visit_file(&parsed_file);
+    Ok(ParsedFile {
+        module_path: files::crate_module_path(settings, file),
+        file: file.clone(),
+        unchecked_functions,
+        definitions: definitions.definitions,
+        call_nodes: visitor.call_nodes,
+        call_sites: visitor.call_sites,
+        re_exports: visitor.re_exports,
+        test_file: is_test_file(file),
+    })
+}
+
+fn check_for_safe_versions(
+    settings: &Settings,
+    parsed_file: &ParsedFile,
+    safe_callers: &[UncheckedFn],
+    index: &HashMap<&str, Vec<IndexEntry>>,
+) -> Vec<Finding> {
+    let mut results = Vec::new();
+    let file = &parsed_file.file;
+    let module_path = &parsed_file.module_path;
+
+    for unchecked_fn in parsed_file.unchecked_functions.iter().chain(safe_callers) {
+        let context = if parsed_file.test_file || unchecked_fn.in_test_fn || cfg::is_test_only(&unchecked_fn.cfg) {
+            Context::Test
+        } else {
+            Context::Code
+        };
+        if (settings.skip_tests && context == Context::Test)
+            || settings.features.as_ref().is_some_and(|features| !cfg::is_active(&unchecked_fn.cfg, features))
+            || (settings.only_const && !unchecked_fn.const_fn)
+            || (settings.only_async && !unchecked_fn.async_fn)
+        {
+            continue;
+        }
+        let unchecked_fn = unchecked_fn.clone();
+        // 安全版本可能的各个名字中找得到定义的
+        let safe_names = naming::safe_name_candidates(&settings.conventions, &unchecked_fn.name);
+        let found: Vec<(String, &Definition)> = safe_names
+            .iter()
+            .filter_map(|safe_name| {
+                let candidates = index.get(safe_name.as_str())?.iter().filter(|entry| {
+                    unchecked_fn.kind.is_call() || entry.owner == unchecked_fn.owner.as_deref()
+                });
+                let definition = closest_definition(candidates, parsed_file)?;
+                Some((safe_name.clone(), definition))
+            })
+            .collect();
+        // 按候选名字的顺序取第一个找到的，但安全的定义优先
+        let counterpart = found.iter().find(|(_, definition)| definition.safe).or(found.first());
+        let safe_counterpart = match counterpart {
+            // 安全版本在 unsafe 代码之前看不出任何验证
+            Some((safe_name, definition)) if definition.safe && definition.validates == Some(false) => {
+                SafeCounterpart::Unvalidated(safe_name.clone())
+            }
+            Some((safe_name, definition)) if definition.safe => SafeCounterpart::Safe(safe_name.clone()),
+            Some((safe_name, _)) => SafeCounterpart::Unsafe(safe_name.clone()),
+            None => SafeCounterpart::Missing,
+        };
+        let definition = counterpart.map(|(_, definition)| *definition);
+        let counterpart_signature = match (&unchecked_fn.shape, definition.and_then(|definition| definition.shape.as_ref())) {
+            (Some(shape), Some(safe_shape)) => Some(shape.compare(safe_shape)),
+            _ => None,
+        };
+        // 安全函数的类别取决于调用链末端的函数
+        let category = match (unchecked_fn.kind, unchecked_fn.call_chain.last()) {
+            (FindingKind::AssumeInit, _) => Category::Uninitialized,
+            (_, Some(callee)) => category::classify(callee, None),
+            _ => category::classify(&unchecked_fn.name, unchecked_fn.owner.as_deref()),
+        };
+        let counterpart_relation = match definition {
+            _ if safe_names.is_empty() => None,
+            Some(definition) if definition.has_body && definition.calls.contains(&unchecked_fn.name) => Some(CounterpartRelation::Wraps),
+            Some(definition) if definition.has_body => Some(CounterpartRelation::Independent),
+            _ => Some(CounterpartRelation::NotFound),
+        };
+        // 安全版本比这个函数多做的检查，两者的函数体中都有的检查不算
+        let added_checks = match definition {
+            Some(definition) if definition.safe && definition.has_body => {
+                let own = index.get(unchecked_fn.name.as_str()).and_then(|entries| {
+                    let candidates = entries.iter().filter(|entry| {
+                        unchecked_fn.kind.is_call() || entry.owner == unchecked_fn.owner.as_deref()
+                    });
+                    closest_definition(candidates, parsed_file)
+                });
+                checks::added(&definition.checks, own.map(|own| own.checks.as_slice()).unwrap_or_default())
+            }
+            _ => Vec::new(),
+        };
+        let module_path = if unchecked_fn.inline_module.is_empty() {
+            module_path.clone()
+        } else {
+            format!("{}::{}", module_path, unchecked_fn.inline_module)
+        };
+        results.push(Finding {
+            root: file.root.clone(),
+            package: settings.members.get(&file.root).map(|member| member.package.clone()),
+            file: file.path.clone(),
+            module_path,
+            function: unchecked_fn.name,
+            name_matches: unchecked_fn.name_matches,
+            owner: unchecked_fn.owner,
+            trait_name: unchecked_fn.trait_name,
+            signature: unchecked_fn.signature,
+            features: cfg::features(&unchecked_fn.cfg),
+            cfg: unchecked_fn.cfg,
+            deprecated: unchecked_fn.deprecated,
+            stability: unchecked_fn.stability,
+            const_fn: unchecked_fn.const_fn,
+            async_fn: unchecked_fn.async_fn,
+            safety_doc: unchecked_fn.safety_doc,
+            safety_section: unchecked_fn.safety_section,
+            debug_checked: unchecked_fn.debug_checked,
+            counterpart_signature,
+            counterpart_relation,
+            added_checks,
+            caller: unchecked_fn.caller,
+            kind: unchecked_fn.kind,
+            context,
+            category,
+            span: unchecked_fn.span,
+            count: unchecked_fn.count,
+            call_chain: unchecked_fn.call_chain,
+            example_calls: unchecked_fn.example_calls,
+            callers: Vec::new(),
+            reexports: Vec::new(),
+            safe_counterpart,
+        });
+    }
+
+    results
+}
+
+// 所有文件中的函数定义按函数名建立的索引中的一项
+struct IndexEntry<'a> {
+    file: &'a str,
+    module_path: &'a str,
+    owner: Option<&'a str>,
+    definition: &'a Definition,
+}
+
+// 优先同一文件中的定义，其次同一 crate 中模块路径与 parsed_file 公共前缀最长的，同样近时优先安全的
+fn closest_definition<'a>(candidates: impl Iterator<Item = &'a IndexEntry<'a>>, parsed_file: &ParsedFile) -> Option<&'a Definition> {
+    let module_path = parsed_file.module_path.as_str();
+    let crate_name = module_path.split("::").next();
+    let common_prefix = |other: &str| module_path.split("::").zip(other.split("::")).take_while(|(a, b)| a == b).count();
+    candidates
+        .filter(|entry| entry.module_path.split("::").next() == crate_name)
+        .max_by_key(|entry| (entry.file == parsed_file.file.path, common_prefix(entry.module_path), entry.definition.safe))
+        .map(|entry| entry.definition)
+}
+
+// 所属的类型、trait 或宏（`名字!`）和函数名；自由函数和外部函数不属于任何类型
+type DefinitionKey = (Option<String>, String);
+
+// 同一类型的同名函数的所有定义合在一起
+#[derive(Default)]
+struct Definition {
+    // 是否至少有一个定义不是 unsafe 的
+    safe: bool,
+    // 第一个安全的定义的签名，都是 unsafe 时取第一个；宏中的函数没有
+    shape: Option<SignatureShape>,
+    // 是否至少有一个定义有函数体，以及所有函数体中调用的函数和做的检查
+    has_body: bool,
+    calls: HashSet<String>,
+    checks: Vec<Check>,
+    // 有 unsafe 块的函数体中是否有一个在第一个 unsafe 块之前做了验证；都没有 unsafe 块时为 None
+    validates: Option<bool>,
+}
+
+// 文件中所有函数定义（包括嵌套在函数、impl、trait 和内联模块中的），按所属类型和函数名合并
+#[derive(Default)]
+struct DefinitionVisitor {
+    definitions: HashMap<DefinitionKey, Definition>,
+    // 正在遍历的 impl 的类型或 trait 的名字
+    owner: Option<String>,
+}
+
+impl DefinitionVisitor {
+    fn add(&mut self, name: &Ident, safe: bool, shape: Option<SignatureShape>) {
+        let definition = self.definitions.entry((self.owner.clone(), name.to_string())).or_default();
+        if shape.is_some() && (definition.shape.is_none() || (safe && !definition.safe)) {
+            definition.shape = shape;
+        }
+        definition.safe |= safe;
+    }
+
+    fn add_body(&mut self, name: &Ident, calls: HashSet<String>, body: BodyChecks) {
+        let definition = self.definitions.entry((self.owner.clone(), name.to_string())).or_default();
+        definition.has_body = true;
+        definition.calls.extend(calls);
+        for check in body.checks {
+            if !definition.checks.contains(&check) {
+                definition.checks.push(check);
+            }
+        }
+        definition.validates = match (definition.validates, body.validates) {
+            (Some(validates), Some(other)) => Some(validates || other),
+            (validates, other) => validates.or(other),
+        };
+    }
+
+    fn add_block(&mut self, name: &Ident, block: &Block) {
+        let mut collector = CallCollector::default();
+        collector.visit_block(block);
+        self.add_body(name, collector.calls, checks::collect(block));
+    }
+}
+
+// 函数体中调用的函数和方法的名字，不包括嵌套定义的函数中的调用
+#[derive(Default)]
+struct CallCollector {
+    calls: HashSet<String>,
+}
+
+impl<'ast> Visit<'ast> for CallCollector {
+    fn visit_expr_call(&mut self, node: &'ast ExprCall) {
+        if let Expr::Path(path) = &*node.func {
+            if let Some(segment) = path.path.segments.last() {
+                self.calls.insert(segment.ident.to_string());
+            }
+        }
+        visit::visit_expr_call(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast ExprMethodCall) {
+        self.calls.insert(node.method.to_string());
+        visit::visit_expr_method_call(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        // 例如 `debug_assert!(..)` 或 `unsafe_impl!(..)` 中的调用
+        self.calls.extend(token_calls(node.tokens.clone()));
+    }
+
+    fn visit_item_fn(&mut self, _node: &'ast ItemFn) {}
+}
+
+impl<'ast> Visit<'ast> for DefinitionVisitor {
+    fn visit_signature(&mut self, node: &'ast Signature) {
+        // 函数、方法和 trait 方法的签名都会经过这里
+        let is_unsafe = node.unsafety.is_some();
+        self.add(&node.ident, !is_unsafe, Some(SignatureShape::new(node, is_unsafe)));
+        visit::visit_signature(self, node);
+    }
+
+    fn visit_item_fn(&mut self, node: &'ast ItemFn) {
+        // 方法中嵌套定义的函数是自由函数
+        let outer = self.owner.take();
+        self.add_block(&node.sig.ident, &node.block);
+        visit::visit_item_fn(self, node);
+        self.owner = outer;
+    }
+
+    fn visit_impl_item_fn(&mut self, node: &'ast ImplItemFn) {
+        self.add_block(&node.sig.ident, &node.block);
+        visit::visit_impl_item_fn(self, node);
+    }
+
+    fn visit_trait_item_fn(&mut self, node: &'ast TraitItemFn) {
+        if let Some(default) = &node.default {
+            self.add_block(&node.sig.ident, default);
+        }
+        visit::visit_trait_item_fn(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        let outer = self.owner.replace(type_name(&node.self_ty));
+        visit::visit_item_impl(self, node);
+        self.owner = outer;
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        let outer = self.owner.replace(node.ident.to_string());
+        visit::visit_item_trait(self, node);
+        self.owner = outer;
+    }
+
+    fn visit_item_macro(&mut self, node: &'ast ItemMacro) {
+        if node.mac.path.is_ident("macro_rules") {
+            // 与 FunctionVisitor 一样，宏中的函数属于宏本身
+            let outer = std::mem::replace(&mut self.owner, node.ident.as_ref().map(|ident| format!("{ident}!")));
+            for MacroFn { ident, is_unsafe, calls } in macro_functions(node.mac.tokens.clone()) {
+                self.add(&ident, !is_unsafe, None);
+                if let Some(calls) = calls {
+                    self.add_body(&ident, calls, BodyChecks::default());
+                }
+            }
+            self.owner = outer;
+        }
+        visit::visit_item_macro(self, node);
+    }
+
+    fn visit_foreign_item_fn(&mut self, node: &'ast ForeignItemFn) {
+        // extern 块中的函数即使没有写 unsafe，调用时也必须在 unsafe 块中
+        let outer = self.owner.take();
+        self.add(&node.sig.ident, false, Some(SignatureShape::new(&node.sig, true)));
+        self.owner = outer;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::checks::CheckKind;
+    use crate::cli::{ScanArgs, ScanOptions};
+    use crate::finding::SignatureComparison;
+
+    // 用给定的规则扫描内存中的源码，路径相对于扫描根目录 `krate`，结果按位置排序
+    fn scan_sources(detect: &[Detect], sources: &[(&str, &str)]) -> Vec<Finding> {
+        let args = ScanArgs {
+            roots: vec![PathBuf::from("krate")],
+            options: ScanOptions { detect: detect.to_vec(), ..ScanOptions::default() },
+            ..ScanArgs::default()
+        };
+        let settings = Settings::resolve(&args).unwrap();
+        let files: Vec<SourceFile> = sources
+            .iter()
+            .map(|(path, source)| SourceFile {
+                root: "krate".to_string(),
+                path: format!("krate/{path}"),
+                content: Some(Arc::from(*source)),
+            })
+            .collect();
+        let mut findings: Vec<Finding> = scan(&settings, &files).unwrap().findings.into_iter().collect();
+        findings.sort_by_key(|finding| (finding.file.clone(), finding.span.line, finding.span.column));
+        findings
+    }
+
+    fn scan_source(detect: &[Detect], source: &str) -> Vec<Finding> {
+        scan_sources(detect, &[("src/lib.rs", source)])
+    }
+
+    fn names(findings: &[Finding]) -> Vec<(String, FindingKind)> {
+        findings.iter().map(|finding| (finding.name(), finding.kind)).collect()
+    }
+
+    #[test]
+    fn trait_and_impl_methods() {
+        let findings = scan_source(&[Detect::Name], "
+            pub trait Get { unsafe fn get_unchecked(&self, i: usize) -> u8; }
+            pub struct V;
+            impl V { pub unsafe fn get_unchecked(&self, i: usize) -> u8 { 0 } }
+            impl Get for V { unsafe fn get_unchecked(&self, i: usize) -> u8 { 0 } }
+        ");
+        assert_eq!(names(&findings), [
+            ("Get::get_unchecked".to_string(), FindingKind::TraitMethod),
+            ("V::get_unchecked".to_string(), FindingKind::Method),
+            ("<V as Get>::get_unchecked".to_string(), FindingKind::Method),
+        ]);
+    }
+
+    #[test]
+    fn nested_functions_and_their_counterparts() {
+        let findings = scan_source(&[Detect::Name], "
+            pub fn outer() {
+                unsafe fn inner_unchecked() {}
+                fn inner() {}
+            }
+        ");
+        assert_eq!(names(&findings), [("inner_unchecked".to_string(), FindingKind::Function)]);
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Safe("inner".to_string()));
+    }
+
+    #[test]
+    fn functions_in_macro_rules_bodies() {
+        let findings = scan_source(&[Detect::Name], "
+            macro_rules! accessors {
+                () => {
+                    pub unsafe fn read_unchecked() {}
+                    pub fn read() {}
+                };
+            }
+            pub unsafe fn read_unchecked() {}
+        ");
+        assert_eq!(names(&findings), [
+            ("accessors!::read_unchecked".to_string(), FindingKind::MacroGenerated),
+            ("read_unchecked".to_string(), FindingKind::Function),
+        ]);
+        // 宏中的安全版本只对同一个宏中的函数有效
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Safe("read".to_string()));
+        assert_eq!(findings[1].safe_counterpart, SafeCounterpart::Missing);
+    }
+
+    #[test]
+    fn declarations_in_extern_blocks() {
+        let findings = scan_source(&[Detect::Name], r#"
+            extern "C" {
+                fn memcpy_unchecked(dst: *mut u8, src: *const u8, n: usize);
+                fn memcpy(dst: *mut u8, src: *const u8, n: usize);
+            }
+        "#);
+        assert_eq!(names(&findings), [("memcpy_unchecked".to_string(), FindingKind::Ffi)]);
+        // 外部函数都是 unsafe 的
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Unsafe("memcpy".to_string()));
+    }
+
+    #[test]
+    fn call_sites_of_unchecked_functions() {
+        let findings = scan_source(&[Detect::Calls], "
+            pub unsafe fn at_unchecked(v: &[u8]) -> u8 { 0 }
+            fn first(v: &[u8]) -> u8 {
+                unsafe {
+                    if v.is_empty() { core::hint::unreachable_unchecked() }
+                    at_unchecked(v) + *v.get_unchecked(0)
+                }
+            }
+        ");
+        let calls: Vec<(&str, FindingKind, Option<&str>)> = findings
+            .iter()
+            .map(|finding| (finding.function.as_str(), finding.kind, finding.caller.as_deref()))
+            .collect();
+        assert_eq!(calls, [
+            ("unreachable_unchecked", FindingKind::Call, Some("first")),
+            ("at_unchecked", FindingKind::Call, Some("first")),
+            ("get_unchecked", FindingKind::Call, Some("first")),
+        ]);
+    }
+
+    #[test]
+    fn union_definitions_and_field_reads() {
+        let findings = scan_source(&[Detect::Unions], "
+            union Bits { int: u32, float: f32 }
+            fn convert() -> f32 {
+                let mut bits = Bits { int: 1 };
+                bits.int = 2;
+                unsafe { bits.float }
+            }
+        ");
+        // 写入字段是安全的，不报告
+        assert_eq!(names(&findings), [
+            ("Bits".to_string(), FindingKind::Union),
+            ("float in convert".to_string(), FindingKind::UnionAccess),
+        ]);
+    }
+
+    #[test]
+    fn signatures_are_compared_with_the_counterpart() {
+        let findings = scan_source(&[Detect::Name], "
+            pub struct V;
+            impl V {
+                pub unsafe fn get_unchecked(&self, i: usize) -> u8 { 0 }
+                pub fn get(&self, i: usize) -> Option<u8> { None }
+                pub unsafe fn len_unchecked(&self) -> usize { 0 }
+                pub fn len(&self) -> usize { 0 }
+                pub unsafe fn at_unchecked(&self, i: usize) -> u8 { 0 }
+                pub fn at(&self, i: u32) -> u8 { 0 }
+                pub unsafe fn id_unchecked(&self) -> u8 { 0 }
+                pub unsafe fn id(&self) -> u8 { 0 }
+                pub unsafe fn raw_unchecked(&self) -> u8 { 0 }
+            }
+        ");
+        let comparisons: Vec<(&str, Option<SignatureComparison>)> = findings
+            .iter()
+            .map(|finding| (finding.function.as_str(), finding.counterpart_signature))
+            .collect();
+        assert_eq!(comparisons, [
+            ("get_unchecked", Some(SignatureComparison::Fallible)),
+            ("len_unchecked", Some(SignatureComparison::DropsUnsafe)),
+            ("at_unchecked", Some(SignatureComparison::Different)),
+            ("id_unchecked", Some(SignatureComparison::Identical)),
+            ("raw_unchecked", None),
+        ]);
+    }
+
+    #[test]
+    fn counterparts_in_other_files_of_the_crate() {
+        let findings = scan_sources(&[Detect::Name], &[
+            ("src/lib.rs", "pub mod io; pub unsafe fn read_unchecked() {}"),
+            ("src/io.rs", "pub fn read() {}"),
+        ]);
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Safe("read".to_string()));
+    }
+
+    #[test]
+    fn closest_definition_wins_over_a_safe_one() {
+        // 同一文件中的定义优先
+        let findings = scan_sources(&[Detect::Name], &[
+            ("src/a.rs", "pub unsafe fn at_unchecked(i: usize) -> u8 { 0 }\npub unsafe fn at(i: usize) -> u8 { 0 }"),
+            ("src/b.rs", "pub fn at(i: usize) -> u8 { 0 }"),
+        ]);
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Unsafe("at".to_string()));
+
+        // 其次是模块路径的公共前缀最长的
+        let findings = scan_sources(&[Detect::Name], &[
+            ("src/a/mod.rs", "pub mod raw; pub unsafe fn at_unchecked(i: usize) -> u8 { 0 }"),
+            ("src/a/raw.rs", "pub unsafe fn at(i: usize) -> u8 { 0 }"),
+            ("src/b.rs", "pub fn at(i: usize) -> u8 { 0 }"),
+        ]);
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Unsafe("at".to_string()));
+
+        // 同样近时安全的优先
+        let findings = scan_sources(&[Detect::Name], &[
+            ("src/a/mod.rs", "pub mod raw; pub mod checked; pub unsafe fn at_unchecked(i: usize) -> u8 { 0 }"),
+            ("src/a/raw.rs", "pub unsafe fn at(i: usize) -> u8 { 0 }"),
+            ("src/a/checked.rs", "pub fn at(i: usize) -> u8 { 0 }"),
+        ]);
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Safe("at".to_string()));
+    }
+
+    #[test]
+    fn counterparts_in_other_crates_are_not_used() {
+        let findings = scan_sources(&[Detect::Name], &[
+            ("core/src/lib.rs", "pub unsafe fn read_unchecked() {}"),
+            ("alloc/src/lib.rs", "pub fn read() {}"),
+        ]);
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Missing);
+    }
+
+    #[test]
+    fn method_counterparts_belong_to_the_same_type() {
+        // 同一类型的方法可以在另一个文件的 impl 块中
+        let findings = scan_sources(&[Detect::Name], &[
+            ("src/lib.rs", "pub mod a; pub mod b;"),
+            ("src/a.rs", "pub struct S; impl S { pub unsafe fn get_unchecked(&self) {} }"),
+            ("src/b.rs", "pub struct T; impl T { pub fn get(&self) {} }\npub fn get() {}\nimpl crate::a::S { pub fn get(&self) {} }"),
+        ]);
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Safe("get".to_string()));
+
+        // 其他类型的方法和自由函数不算
+        let findings = scan_sources(&[Detect::Name], &[
+            ("src/lib.rs", "pub struct S; impl S { pub unsafe fn get_unchecked(&self) {} }"),
+            ("src/b.rs", "pub struct T; impl T { pub fn get(&self) {} }\npub fn get() {}"),
+        ]);
+        assert_eq!(findings[0].safe_counterpart, SafeCounterpart::Missing);
+    }
+
+    #[test]
+    fn pub_use_re_exports_of_free_functions() {
+        let findings = scan_sources(&[Detect::Name], &[
+            ("src/lib.rs", "mod raw;\npub use raw::read_unchecked;"),
+            ("src/raw.rs", "pub unsafe fn read_unchecked() {}\npub struct S; impl S { pub unsafe fn get_unchecked(&self) {} }"),
+        ]);
+        let reexports: Vec<(String, Vec<String>)> = findings
+            .iter()
+            .map(|finding| (finding.name(), finding.reexports.clone()))
+            .collect();
+        assert_eq!(reexports, [
+            ("read_unchecked".to_string(), vec!["krate::read_unchecked".to_string()]),
+            ("S::get_unchecked".to_string(), Vec::new()),
+        ]);
+    }
+
+    #[test]
+    fn checks_the_counterpart_adds() {
+        let findings = scan_source(&[Detect::Name], "
+            pub struct V(Vec<u8>);
+            impl V {
+                pub unsafe fn get_unchecked(&self, i: usize) -> u8 {
+                    assert!(!self.0.is_empty());
+                    *self.0.as_ptr().add(i)
+                }
+                pub fn get(&self, i: usize) -> Option<u8> {
+                    assert!(!self.0.is_empty());
+                    if i < self.0.len() { Some(unsafe { self.get_unchecked(i) }) } else { None }
+                }
+            }
+        ");
+        // 两者都有的 assert! 不算
+        let checks: Vec<(CheckKind, &str)> = findings[0].added_checks.iter().map(|check| (check.kind, check.code.as_str())).collect();
+        assert_eq!(checks, [(CheckKind::Bounds, "i < self.0.len()"), (CheckKind::Failure, "None")]);
+    }
+}
@@ -0,0 +1,61 @@
+use quote::ToTokens;
+use syn::{FnArg, ReturnType, Signature, Type};
+
+use crate::finding::SignatureComparison;
+
+/// The parts of a function signature compared between an unchecked function
+/// and its safe counterpart. Types are kept as written, so two spellings of
+/// the same type count as different.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SignatureShape {
+    is_unsafe: bool,
+    inputs: Vec<String>,
+    output: String,
+    // 返回 Option 或 Result
+    fallible: bool,
+}
+
+impl SignatureShape {
+    // extern 块中的函数没有写 unsafe 也是 unsafe 的，由调用者传入
+    pub fn new(sig: &Signature, is_unsafe: bool) -> Self {
+        let inputs = sig
+            .inputs
+            .iter()
+            .map(|input| match input {
+                FnArg::Receiver(receiver) => receiver.to_token_stream().to_string(),
+                FnArg::Typed(typed) => typed.ty.to_token_stream().to_string(),
+            })
+            .collect();
+        let (output, fallible) = match &sig.output {
+            ReturnType::Default => (String::new(), false),
+            ReturnType::Type(_, ty) => (ty.to_token_stream().to_string(), is_fallible(ty)),
+        };
+        SignatureShape { is_unsafe, inputs, output, fallible }
+    }
+
+    /// How the safe counterpart's signature differs from this one's.
+    pub fn compare(&self, safe: &SignatureShape) -> SignatureComparison {
+        if safe.fallible && !self.fallible {
+            SignatureComparison::Fallible
+        } else if self.inputs != safe.inputs || self.output != safe.output {
+            SignatureComparison::Different
+        } else if self.is_unsafe && !safe.is_unsafe {
+            SignatureComparison::DropsUnsafe
+        } else {
+            SignatureComparison::Identical
+        }
+    }
+}
+
+fn is_fallible(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option" || segment.ident == "Result"),
+        Type::Paren(paren) => is_fallible(&paren.elem),
+        Type::Group(group) => is_fallible(&group.elem),
+        _ => false,
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::Result;
+
+use crate::cli::StatsArgs;
+use crate::report::{self, Summary};
+
+/// Prints how many unchecked functions a report contains, broken down by the
+/// status of their safe counterpart and by scan root.
+pub fn run(args: &StatsArgs) -> Result<()> {
+    let (findings, file_stats) = report::read_json_with_stats(&args.input)?;
+
+    let summary = Summary::new(&findings);
+    let files: HashSet<&str> = findings.iter().map(|finding| finding.file.as_str()).collect();
+
+    println!("Unchecked functions:        {}", summary.total);
+    println!("  with a safe version:      {} ({:.1}%)", summary.safe, summary.coverage());
+    println!("  with an unsafe version:   {}", summary.unsafe_);
+    println!("  without a safe version:   {}", summary.missing);
+    if summary.unvalidated > 0 {
+        println!("  safe but no validation:   {}", summary.unvalidated);
+    }
+    if !summary.categories.is_empty() {
+        println!("By category:                {}", summary.category_breakdown());
+    }
+    if summary.deprecated > 0 {
+        println!("Deprecated (not counted):   {}", summary.deprecated);
+    }
+    let other_findings = summary.kind_breakdown();
+    if !other_findings.is_empty() {
+        println!("Other findings:             {other_findings}");
+    }
+    if summary.unsafe_blocks > 0 {
+        println!("Unsafe blocks:              {}", summary.unsafe_blocks);
+    }
+    println!("Files with findings:        {}", files.len());
+    if let Some(file_stats) = file_stats {
+        println!("Files scanned:              {}", file_stats.scanned);
+        println!("Files skipped:              {}", file_stats.skipped);
+    }
+
+    // 只有一个根目录时按根目录统计没有意义
+    let mut by_root: BTreeMap<&str, usize> = BTreeMap::new();
+    for finding in &findings {
+        *by_root.entry(finding.root.as_str()).or_default() += 1;
+    }
+    if by_root.len() > 1 {
+        println!("By root:");
+        for (root, count) in by_root {
+            println!("  {root}: {count}");
+        }
+    }
+
+    Ok(())
+}
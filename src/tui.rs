@@ -0,0 +1,287 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::baseline::{self, Key};
+use crate::cli::TuiArgs;
+use crate::finding::{Finding, SafeCounterpart};
+use crate::report;
+
+const HELP: &str = " ↑/↓ move  f filter  space reviewed  h hide reviewed  q quit ";
+
+/// Opens an interactive browser for a JSON report. Findings are grouped by
+/// file and the source around the selected function is shown next to them.
+///
+/// Reviewed findings are saved to `args.reviewed` (by default the report path
+/// with `.reviewed.json` appended) every time one is marked.
+pub fn run(args: &TuiArgs) -> Result<()> {
+    let mut findings: Vec<Finding> = report::read_json(&args.input)?.into_iter().collect();
+    findings.sort_by(|a, b| (&a.file, a.span.line).cmp(&(&b.file, b.span.line)));
+
+    let reviewed_path = args.reviewed.clone().unwrap_or_else(|| {
+        let mut path = args.input.clone().into_os_string();
+        path.push(".reviewed.json");
+        PathBuf::from(path)
+    });
+    let reviewed = load_reviewed(&reviewed_path)?;
+
+    let mut app = App {
+        findings,
+        reviewed,
+        reviewed_path,
+        filter: Filter::All,
+        hide_reviewed: false,
+        rows: Vec::new(),
+        state: ListState::default(),
+        sources: HashMap::new(),
+    };
+    app.rebuild_rows();
+
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+// 已审阅的结果以 (文件, 函数名, 种类) 列表的形式保存为 json
+fn load_reviewed(path: &Path) -> Result<HashSet<Key>> {
+    if !path.exists() {
+        return Ok(HashSet::new());
+    }
+    let content = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let reviewed: Vec<Key> =
+        serde_json::from_str(&content).with_context(|| format!("invalid reviewed list {}", path.display()))?;
+    Ok(reviewed.into_iter().collect())
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    All,
+    WithoutSafe,
+    WithSafe,
+}
+
+impl Filter {
+    fn next(self) -> Self {
+        match self {
+            Filter::All => Filter::WithoutSafe,
+            Filter::WithoutSafe => Filter::WithSafe,
+            Filter::WithSafe => Filter::All,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Filter::All => "all",
+            Filter::WithoutSafe => "without safe version",
+            Filter::WithSafe => "with safe version",
+        }
+    }
+
+    fn accepts(self, finding: &Finding) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::WithoutSafe => !finding.safe_counterpart.is_safe(),
+            Filter::WithSafe => finding.safe_counterpart.is_safe(),
+        }
+    }
+}
+
+// 列表中的一行：文件标题行，或者指向 findings 中某一项的结果行
+enum Row {
+    File(String),
+    Finding(usize),
+}
+
+struct App {
+    findings: Vec<Finding>,
+    reviewed: HashSet<Key>,
+    reviewed_path: PathBuf,
+    filter: Filter,
+    hide_reviewed: bool,
+    rows: Vec<Row>,
+    state: ListState,
+    // 文件内容按行缓存，读取失败时为 None
+    sources: HashMap<String, Option<Vec<String>>>,
+}
+
+impl App {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::PageDown => self.move_selection(20),
+                KeyCode::PageUp => self.move_selection(-20),
+                KeyCode::Home | KeyCode::Char('g') => self.move_selection(-(self.rows.len() as isize)),
+                KeyCode::End | KeyCode::Char('G') => self.move_selection(self.rows.len() as isize),
+                KeyCode::Char('f') => {
+                    self.filter = self.filter.next();
+                    self.rebuild_rows();
+                }
+                KeyCode::Char('h') => {
+                    self.hide_reviewed = !self.hide_reviewed;
+                    self.rebuild_rows();
+                }
+                KeyCode::Char(' ') => self.toggle_reviewed()?,
+                _ => {}
+            }
+        }
+    }
+
+    fn is_reviewed(&self, finding: &Finding) -> bool {
+        self.reviewed.contains(&baseline::key(finding))
+    }
+
+    fn selected_finding(&self) -> Option<usize> {
+        match self.rows.get(self.state.selected()?)? {
+            Row::Finding(index) => Some(*index),
+            Row::File(_) => None,
+        }
+    }
+
+    // 过滤条件变化后重新生成列表，并尽量保持原来选中的结果
+    fn rebuild_rows(&mut self) {
+        let selected = self.selected_finding();
+        self.rows.clear();
+        let mut current_file: Option<&str> = None;
+        for (index, finding) in self.findings.iter().enumerate() {
+            if !self.filter.accepts(finding) || (self.hide_reviewed && self.is_reviewed(finding)) {
+                continue;
+            }
+            if current_file != Some(finding.file.as_str()) {
+                current_file = Some(finding.file.as_str());
+                self.rows.push(Row::File(finding.file.clone()));
+            }
+            self.rows.push(Row::Finding(index));
+        }
+
+        let position = selected
+            .and_then(|selected| self.rows.iter().position(|row| matches!(row, Row::Finding(index) if *index == selected)))
+            .or_else(|| self.rows.iter().position(|row| matches!(row, Row::Finding(_))));
+        self.state.select(position);
+    }
+
+    // 移动选中项，跳过文件标题行
+    fn move_selection(&mut self, delta: isize) {
+        let Some(current) = self.state.selected() else {
+            return;
+        };
+        let finding_rows: Vec<usize> = self.rows.iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, Row::Finding(_)))
+            .map(|(position, _)| position)
+            .collect();
+        let Some(current) = finding_rows.iter().position(|&position| position == current) else {
+            return;
+        };
+        let target = (current as isize + delta).clamp(0, finding_rows.len() as isize - 1);
+        self.state.select(Some(finding_rows[target as usize]));
+    }
+
+    fn toggle_reviewed(&mut self) -> Result<()> {
+        let Some(index) = self.selected_finding() else {
+            return Ok(());
+        };
+        let finding = &self.findings[index];
+        // 同一文件中不同类型的同名方法、同名函数的定义和调用分别标记
+        let key = baseline::key(finding);
+        if !self.reviewed.remove(&key) {
+            self.reviewed.insert(key);
+        }
+
+        let mut reviewed: Vec<&Key> = self.reviewed.iter().collect();
+        reviewed.sort();
+        let content = serde_json::to_string_pretty(&reviewed)?;
+        fs::write(&self.reviewed_path, content)
+            .with_context(|| format!("failed to write {}", self.reviewed_path.display()))?;
+
+        if self.hide_reviewed {
+            // 被隐藏前先移到下一项，避免选中项跳回开头
+            self.move_selection(1);
+            self.rebuild_rows();
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [list_area, source_area] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)]).areas(main);
+
+        let items: Vec<ListItem> = self.rows.iter().map(|row| match row {
+            Row::File(file) => ListItem::new(Line::from(Span::styled(
+                file.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ))),
+            Row::Finding(index) => {
+                let finding = &self.findings[*index];
+                let mark = if self.is_reviewed(finding) { "[x]" } else { "[ ]" };
+                let color = match finding.safe_counterpart {
+                    SafeCounterpart::Safe(_) => Color::Green,
+                    SafeCounterpart::Unvalidated(_) | SafeCounterpart::Unsafe(_) => Color::Yellow,
+                    SafeCounterpart::Missing => Color::Red,
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("  {mark} {} ", finding.name())),
+                    Span::styled(format!("-> {}", finding.safe_counterpart), Style::default().fg(color)),
+                ]))
+            }
+        }).collect();
+        let title = format!(" Findings ({}) ", self.filter.label());
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, list_area, &mut self.state);
+
+        let source = self.source_context(source_area.height.saturating_sub(2) as usize);
+        frame.render_widget(source, source_area);
+
+        let reviewed = self.findings.iter().filter(|finding| self.is_reviewed(finding)).count();
+        let summary = format!(" {}/{} reviewed |{}", reviewed, self.findings.len(), HELP);
+        frame.render_widget(Paragraph::new(summary).style(Style::default().add_modifier(Modifier::REVERSED)), status);
+    }
+
+    // 显示选中函数定义附近的源码，定义所在行高亮
+    fn source_context(&mut self, height: usize) -> Paragraph<'static> {
+        let Some(index) = self.selected_finding() else {
+            return Paragraph::new("").block(Block::bordered().title(" Source "));
+        };
+        let finding = self.findings[index].clone();
+        let block = Block::bordered().title(format!(" {} ", finding.file));
+        let lines = self.sources
+            .entry(finding.file.clone())
+            .or_insert_with(|| fs::read_to_string(&finding.file).ok().map(|content| content.lines().map(str::to_string).collect()));
+        let Some(lines) = lines else {
+            return Paragraph::new(format!("cannot read {}", finding.file)).block(block);
+        };
+
+        let target = finding.span.line.saturating_sub(1);
+        let start = target.saturating_sub(height / 4);
+        let text: Vec<Line> = lines.iter().enumerate().skip(start).take(height).map(|(number, line)| {
+            let content = format!("{:>5} {}", number + 1, line);
+            if number == target {
+                Line::styled(content, Style::default().add_modifier(Modifier::REVERSED))
+            } else {
+                Line::raw(content)
+            }
+        }).collect();
+        Paragraph::new(text).block(block)
+    }
+}
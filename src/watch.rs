@@ -0,0 +1,102 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::slice;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use anyhow::Result;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::config::Settings;
+use crate::diff;
+use crate::files::{self, SourceFile};
+use crate::scan::{self, ParsedFile};
+
+// 合并这段时间内连续到达的事件，避免保存一次文件触发多次扫描
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Scans once, then rescans changed `.rs` files until interrupted and prints
+/// the findings that appeared (`+`), disappeared (`-`) or changed (`~`).
+///
+/// Only the changed files are parsed again. Safe counterparts can be defined
+/// in other files, so they are looked up again for every finding.
+pub fn run(settings: &Settings) -> Result<()> {
+    let (parsed, _) = scan::parse_files(settings, &files::collect_files(settings)?);
+    let mut parsed_by_file: HashMap<String, ParsedFile> =
+        parsed.into_iter().map(|parsed_file| (parsed_file.file.path.clone(), parsed_file)).collect();
+    let mut findings = scan::resolve(settings, parsed_by_file.values(), |_| Ok(()))?;
+    crate::write_output(&settings.output, settings.format, &findings, &settings.report_options)?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for root in &settings.roots {
+        watcher.watch(root, RecursiveMode::Recursive)?;
+    }
+    log::info!("Watching for changes, press Ctrl-C to stop");
+
+    loop {
+        let mut changed = HashSet::new();
+        add_changed_paths(&mut changed, rx.recv()??);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            add_changed_paths(&mut changed, event?);
+        }
+
+        let mut reparsed = false;
+        for path in changed {
+            let Some(file) = source_file_for(settings, &path) else {
+                continue;
+            };
+
+            if Path::new(&file.path).is_file() {
+                let (mut parsed, _) = scan::parse_files(settings, slice::from_ref(&file));
+                // 文件可能正在编辑中，解析失败时保留上一次的结果
+                let Some(parsed_file) = parsed.pop() else {
+                    continue;
+                };
+                parsed_by_file.insert(file.path, parsed_file);
+            } else {
+                parsed_by_file.remove(&file.path);
+            }
+            reparsed = true;
+        }
+        if !reparsed {
+            continue;
+        }
+
+        let new_findings = scan::resolve(settings, parsed_by_file.values(), |_| Ok(()))?;
+        // 只有位置变化的结果不算作变化，但报告中的行号需要更新
+        if new_findings == findings {
+            continue;
+        }
+        diff::print_changes(&diff::compare(&findings, &new_findings));
+        findings = new_findings;
+        crate::write_output(&settings.output, settings.format, &findings, &settings.report_options)?;
+    }
+}
+
+// 扫描时读取文件本身也会产生 Access 事件，只关心真正改变文件的事件
+fn add_changed_paths(changed: &mut HashSet<PathBuf>, event: Event) {
+    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+        changed.extend(event.paths);
+    }
+}
+
+// 把事件中的路径还原成初次扫描时使用的形式（根目录 + 相对路径），
+// 不需要扫描的路径返回 None
+fn source_file_for(settings: &Settings, path: &Path) -> Option<SourceFile> {
+    settings.roots.iter().find_map(|root| {
+        let relative_path = path.strip_prefix(root).ok().map(Path::to_path_buf).or_else(|| {
+            let canonical_root = root.canonicalize().ok()?;
+            path.strip_prefix(canonical_root).ok().map(Path::to_path_buf)
+        })?;
+        if !files::accepts(settings, &relative_path) {
+            return None;
+        }
+        let path: PathBuf = root.join(relative_path);
+        Some(SourceFile {
+            root: root.display().to_string(),
+            path: path.display().to_string(),
+            content: None,
+        })
+    })
+}
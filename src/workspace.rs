@@ -0,0 +1,112 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use cargo_metadata::{MetadataCommand, Target};
+
+use crate::finding::Package;
+
+/// A source directory of a workspace member, scanned as one ROOT with
+/// `--workspace`.
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub root: PathBuf,
+    pub package: Package,
+    // 库目标的 crate 名，没有库时是包名，连字符换成下划线；用作模块路径的第一段
+    pub crate_name: String,
+}
+
+/// The source directories of the members of the Cargo workspaces containing
+/// each of `dirs`, as listed by `cargo metadata`: the directories holding the
+/// crate roots of their library and binary targets, usually `src/`. With
+/// `with_deps`, the packages they depend on according to `Cargo.lock` are
+/// included too, from wherever Cargo keeps their sources (for crates.io,
+/// `~/.cargo/registry/src`).
+/// Members below the current directory are returned relative to it, so
+/// that reported paths look the same as when they are passed as ROOT.
+pub fn members(dirs: &[PathBuf], with_deps: bool) -> Result<Vec<Member>> {
+    let current_dir = env::current_dir()?;
+    let mut members: Vec<Member> = Vec::new();
+    for dir in dirs {
+        for mut member in workspace_members(dir, with_deps)? {
+            // 相对路径中要保留包的目录，模块路径从 src 前面的目录名得到 crate 名
+            if let Ok(relative) = member.root.strip_prefix(&current_dir) {
+                if relative.components().count() > 1 {
+                    member.root = relative.to_path_buf();
+                }
+            }
+            if !members.iter().any(|other| other.root == member.root) {
+                members.push(member);
+            }
+        }
+    }
+    Ok(members)
+}
+
+/// Whether `cargo metadata` can read the package or workspace in `dir`.
+pub fn is_readable(dir: &Path) -> bool {
+    MetadataCommand::new().current_dir(dir).no_deps().exec().is_ok()
+}
+
+// 包按 cargo metadata 列出的顺序；测试、示例、基准和 build.rs 不算源码目录
+fn workspace_members(dir: &Path, with_deps: bool) -> Result<Vec<Member>> {
+    let mut command = MetadataCommand::new();
+    command.current_dir(dir);
+    if !with_deps {
+        command.no_deps();
+    }
+    let metadata = command
+        .exec()
+        .with_context(|| format!("--workspace needs a Cargo workspace, cargo metadata failed in {}", dir.display()))?;
+
+    let mut members = Vec::new();
+    let mut dependencies = 0;
+    for package in &metadata.packages {
+        // 不带 --no-deps 时 packages 中还有 Cargo.lock 中的所有依赖
+        let dependency = !metadata.workspace_members.contains(&package.id);
+        dependencies += usize::from(dependency);
+        let is_source = |target: &&Target| {
+            !(target.is_example() || target.is_test() || target.is_bench() || target.is_custom_build())
+        };
+        let library = package.targets.iter().filter(is_source).find(|target| !target.is_bin());
+        let crate_name = library.map_or(package.name.to_string(), |target| target.name.clone()).replace('-', "_");
+        let mut roots: Vec<PathBuf> = package
+            .targets
+            .iter()
+            .filter(is_source)
+            .filter_map(|target| target.src_path.parent().map(|parent| parent.as_std_path().to_path_buf()))
+            .collect();
+        roots.sort();
+        roots.dedup();
+        // src/bin 已经包含在 src 中
+        let nested: Vec<PathBuf> =
+            roots.iter().filter(|root| roots.iter().any(|other| other != *root && root.starts_with(other))).cloned().collect();
+        roots.retain(|root| !nested.contains(root) && root.is_dir());
+        for root in roots {
+            members.push(Member {
+                root,
+                package: Package {
+                    name: package.name.to_string(),
+                    version: package.version.to_string(),
+                    edition: package.edition.as_str().to_string(),
+                    dependency,
+                },
+                crate_name: crate_name.clone(),
+            });
+        }
+    }
+    if members.is_empty() {
+        bail!("no member of the workspace in {} has a library or binary target", dir.display());
+    }
+    if with_deps {
+        log::info!(
+            "Scanning {} workspace members and {} dependencies of {}",
+            metadata.workspace_members.len(),
+            dependencies,
+            dir.display()
+        );
+    } else {
+        log::info!("Scanning {} workspace members in {}", metadata.workspace_members.len(), dir.display());
+    }
+    Ok(members)
+}